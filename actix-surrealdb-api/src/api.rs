@@ -1,49 +1,1003 @@
 use actix_web::{
-    delete,
-    get,
-    patch,
-    post,
-    web,
-    web::{Json, Path},
-    // HttpResponse,
+    delete, get,
+    http::{header, StatusCode},
+    patch, post, web,
+    web::{Bytes, Json, Path},
+    HttpRequest, HttpResponse, HttpResponseBuilder,
 };
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
 use surrealdb::sql::Thing;
+use tokio::sync::broadcast::error::RecvError;
+use utoipa::{IntoParams, ToSchema};
 
+use crate::backup;
+use crate::config::CONFIG;
+use crate::csv;
+use crate::currency::Conversion;
 use crate::db::*;
+use crate::etag;
+use crate::live;
 use crate::prelude::*;
+use crate::report;
+use types::interest::{CalcOptions, FiscalYearInterest, ProjectedValue, ScheduleEntry, WhatIf};
+use types::view::InvestmentView;
 use types::*;
 
+/// Header naming a create, so that retrying it returns the record the first
+/// attempt made instead of a duplicate. Up to 64 letters, digits, `_` or `-`,
+/// as for an id the client picks.
+pub const IDEMPOTENCY_KEY_HEADER: &str = "Idempotency-Key";
+
+#[utoipa::path(
+    tag = "investments",
+    request_body = Investment,
+    params(("Idempotency-Key" = Option<String>, Header, description = "Makes retrying the create safe")),
+    responses(
+        (status = 200, description = "The investment as stored", body = Investment),
+        (status = 400, description = "The idempotency key is malformed", body = Problem),
+        (status = 409, description = "The id or idempotency key names a deleted investment", body = Problem),
+        (status = 422, description = "The investment failed validation", body = Problem),
+    )
+)]
 #[post("/inv")]
-pub async fn create(inv: web::Json<Investment>) -> Result<Json<Investment>> {
+pub async fn create(req: HttpRequest, inv: web::Json<Investment>) -> Result<Json<Investment>> {
     let mut inv = inv.into_inner();
+    if let Some(key) = req.headers().get(IDEMPOTENCY_KEY_HEADER) {
+        let key = key
+            .to_str()
+            .map_err(|_| Error::BadRequest(format!("Invalid {IDEMPOTENCY_KEY_HEADER}")))?;
+        // the key becomes the record's id, which add_inv already creates only once
+        let id = Thing::from((INVESTMENT, key));
+        if inv.id.as_ref().is_some_and(|given| *given != id) {
+            return Err(Error::BadRequest(format!(
+                "The id and {IDEMPOTENCY_KEY_HEADER} name different investments"
+            )));
+        }
+        inv.id = Some(id);
+    }
     let todo = add_inv(&mut inv).await?;
     Ok(Json(todo))
 }
 
+/// Creates many investments in one transaction, e.g. when moving over from a spreadsheet.
+#[utoipa::path(
+    tag = "investments",
+    request_body = Vec<Investment>,
+    responses((status = 200, description = "One result per investment sent", body = Vec<BatchItem>))
+)]
+#[post("/invs/batch")]
+pub async fn batch(investments: web::Json<Vec<Investment>>) -> Result<Json<Vec<BatchItem>>> {
+    let results = add_invs(investments.into_inner()).await?;
+
+    Ok(Json(results))
+}
+
+#[utoipa::path(
+    tag = "investments",
+    params(("id" = String, Path, description = "Id of the investment, e.g. `investment:abc`")),
+    responses(
+        (status = 200, description = "The investment, with its `ETag`", body = Investment),
+        (status = 304, description = "It still matches the `If-None-Match` sent"),
+        (status = 404, description = "There is no such investment", body = Problem),
+    )
+)]
 #[get("/inv/{id}")]
-pub async fn get(id: Path<String>) -> Result<Json<Investment>> {
-    let task = get_inv(id.into_inner()).await?;
+pub async fn get(req: HttpRequest, id: Path<String>) -> Result<HttpResponse> {
+    let mut task = get_inv(id.into_inner()).await?;
+    task.fill_status(chrono::Utc::now());
+
+    Ok(etag::investment_response(&req, &task))
+}
+
+#[utoipa::path(
+    tag = "investments",
+    params(("id" = String, Path, description = "Id of the investment, e.g. `investment:abc`")),
+    responses(
+        (status = 200, description = "Interest paid or accrued per year of the term", body = Vec<ScheduleEntry>),
+        (status = 404, description = "There is no such investment", body = Problem),
+    )
+)]
+#[get("/inv/{id}/schedule")]
+pub async fn schedule(id: Path<String>) -> Result<Json<Vec<ScheduleEntry>>> {
+    let inv = get_inv(id.into_inner()).await?;
+
+    Ok(Json(inv.schedule_with(&CONFIG.calc_options())))
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct DuplicateQuery {
+    name: String,
+    amount: i64,
+    /// The start date, e.g. 2026-06-30.
+    start: String,
+}
+
+/// Existing investments the one about to be created may duplicate.
+#[utoipa::path(
+    tag = "investments",
+    params(DuplicateQuery),
+    responses((status = 200, description = "Possible duplicates; empty when there are none", body = Vec<Investment>))
+)]
+#[get("/invs/check-duplicate")]
+pub async fn check_duplicate(query: web::Query<DuplicateQuery>) -> Result<Json<Vec<Investment>>> {
+    let duplicates = find_duplicates(&query.name, query.amount, &query.start).await?;
+
+    Ok(Json(duplicates))
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct SearchQuery {
+    q: String,
+}
+
+/// Investments whose name or investor matches `q`, best match first.
+#[utoipa::path(
+    tag = "investments",
+    params(SearchQuery),
+    responses((status = 200, description = "The matches, best first", body = Vec<Investment>))
+)]
+#[get("/invs/search")]
+pub async fn search(query: web::Query<SearchQuery>) -> Result<Json<Vec<Investment>>> {
+    let mut matches = search_invs(&query.q).await?;
+    let now = chrono::Utc::now();
+    for inv in &mut matches {
+        inv.fill_status(now);
+    }
+
+    Ok(Json(matches))
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ValueAtQuery {
+    /// A calendar date, e.g. 2026-06-30.
+    date: String,
+}
+
+#[utoipa::path(
+    tag = "investments",
+    params(("id" = String, Path, description = "Id of the investment, e.g. `investment:abc`"), ValueAtQuery),
+    responses(
+        (status = 200, description = "What the investment is worth on that date", body = ProjectedValue),
+        (status = 400, description = "The date is malformed", body = Problem),
+        (status = 404, description = "There is no such investment", body = Problem),
+    )
+)]
+#[get("/inv/{id}/value-at")]
+pub async fn value_at(
+    id: Path<String>,
+    query: web::Query<ValueAtQuery>,
+) -> Result<Json<ProjectedValue>> {
+    let date = chrono::NaiveDate::parse_from_str(&query.date, "%Y-%m-%d")
+        .map_err(|_| Error::BadRequest(format!("Invalid date: {}", query.date)))?;
+    let inv = get_inv(id.into_inner()).await?;
+
+    let at = date.and_hms_opt(0, 0, 0).unwrap_or_default().and_utc();
+    let value = inv.value_at_with(at, &CONFIG.calc_options());
+
+    Ok(Json(ProjectedValue { date, value }))
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct WhatIfRequest {
+    /// Annual rate to try, as a percentage, e.g. 8 or 7.25.
+    return_rate: f64,
+}
+
+/// Recomputes maturity for another rate; nothing is saved.
+#[utoipa::path(
+    tag = "investments",
+    params(("id" = String, Path, description = "Id of the investment, e.g. `investment:abc`")),
+    request_body = WhatIfRequest,
+    responses(
+        (status = 200, description = "Maturity at the stored and the tried rate", body = WhatIf),
+        (status = 400, description = "The rate is out of range", body = Problem),
+        (status = 404, description = "There is no such investment", body = Problem),
+    )
+)]
+#[post("/inv/{id}/what-if")]
+pub async fn what_if(id: Path<String>, body: web::Json<WhatIfRequest>) -> Result<Json<WhatIf>> {
+    let rate = body.return_rate;
+    if !(rate > 0.0 && rate <= 100.0) {
+        return Err(Error::BadRequest(
+            "return_rate must be above 0 and at most 100".into(),
+        ));
+    }
+    let inv = get_inv(id.into_inner()).await?;
+    let basis_points = (rate * 100.0).round() as i64;
+
+    Ok(Json(inv.what_if_with(basis_points, &CONFIG.calc_options())))
+}
 
-    Ok(Json(task))
+#[utoipa::path(
+    tag = "investments",
+    params(("id" = String, Path, description = "Id of the investment, e.g. `investment:abc`")),
+    responses((status = 200, description = "Every earlier state of the investment, newest first", body = Vec<AuditEntry>))
+)]
+#[get("/inv/{id}/history")]
+pub async fn history(id: Path<String>) -> Result<Json<Vec<AuditEntry>>> {
+    let history = get_inv_history(id.into_inner()).await?;
+
+    Ok(Json(history))
 }
 
+#[utoipa::path(
+    tag = "investments",
+    params(("id" = String, Path, description = "Id of the investment, e.g. `investment:abc`")),
+    responses(
+        (status = 200, description = "The investment with its computed figures and schedule", body = InvestmentView),
+        (status = 404, description = "There is no such investment", body = Problem),
+    )
+)]
+#[get("/inv/{id}/view")]
+pub async fn view(id: Path<String>) -> Result<Json<InvestmentView>> {
+    let inv = get_inv(id.into_inner()).await?;
+
+    Ok(Json(InvestmentView::detailed(
+        inv,
+        chrono::Utc::now(),
+        &CONFIG.calc_options(),
+    )))
+}
+
+/// Takes a whole investment or just some of its fields, with its `id`. Fields
+/// left out are kept; unknown fields are rejected. 409 when the `updated_at`
+/// sent is older than the stored one, 412 when an `If-Match` sent no longer holds.
+#[utoipa::path(
+    tag = "investments",
+    request_body(content = Object, description = "The `id` and the fields to change"),
+    responses(
+        (status = 200, description = "The investment as stored", body = Investment),
+        (status = 404, description = "There is no such investment", body = Problem),
+        (status = 409, description = "It changed since the `updated_at` sent", body = Problem),
+        (status = 412, description = "It no longer matches the `If-Match` sent", body = Problem),
+        (status = 422, description = "The result failed validation", body = Problem),
+    )
+)]
 #[patch("/inv")]
-pub async fn update(inv: web::Json<Investment>) -> Result<Json<Investment>> {
-    let mut inv = inv.into_inner();
-    let updated = update_inv(&mut inv).await?;
+pub async fn update(req: HttpRequest, patch: web::Json<Value>) -> Result<HttpResponse> {
+    let id = patch
+        .get("id")
+        .and_then(|id| serde_json::from_value::<Thing>(id.clone()).ok());
+    if let Some(id) = id {
+        check_precondition(&req, id.to_string()).await?;
+    }
+    let updated = update_inv_fields(patch.into_inner()).await?;
+
+    Ok(etag::investment_response(&req, &updated))
+}
+
+#[utoipa::path(
+    tag = "investments",
+    params(("id" = String, Path, description = "Id of the investment, e.g. `investment:abc`")),
+    request_body(content = Object, description = "A JSON merge patch of the fields to change"),
+    responses(
+        (status = 200, description = "The investment as stored", body = Investment),
+        (status = 404, description = "There is no such investment", body = Problem),
+        (status = 409, description = "It changed since the `updated_at` sent", body = Problem),
+        (status = 412, description = "It no longer matches the `If-Match` sent", body = Problem),
+        (status = 422, description = "The result failed validation", body = Problem),
+    )
+)]
+#[patch("/inv/{id}")]
+pub async fn patch(
+    req: HttpRequest,
+    id: Path<String>,
+    fields: web::Json<Value>,
+) -> Result<HttpResponse> {
+    let id = id.into_inner();
+    check_precondition(&req, id.clone()).await?;
+    let updated = patch_inv(id, fields.into_inner()).await?;
+
+    Ok(etag::investment_response(&req, &updated))
+}
 
-    Ok(Json(updated))
+/// Looks the investment up only when the request sends an `If-Match` to check.
+async fn check_precondition(req: &HttpRequest, id: String) -> Result<()> {
+    if req.headers().contains_key(header::IF_MATCH) {
+        etag::check_if_match(req, &get_inv(id).await?)?;
+    }
+
+    Ok(())
 }
 
+#[utoipa::path(
+    tag = "investments",
+    request_body(content = Object, description = "The id of the investment, as listed"),
+    responses(
+        (status = 200, description = "It was deleted", body = AffectedRows),
+        (status = 404, description = "There is no such investment, or it was already deleted", body = Problem),
+    )
+)]
 #[delete("/inv")]
-pub async fn delete(id: web::Json<Thing>) -> Result<Json<Record>> {
+pub async fn delete(id: web::Json<Thing>) -> Result<Json<AffectedRows>> {
     let deleted = delete_inv(id.into_inner()).await?;
 
     Ok(Json(deleted))
 }
 
+/// Body: the ids of the investments to delete, e.g. `["investment:abc"]`.
+#[utoipa::path(
+    tag = "investments",
+    request_body(content = Vec<String>, description = "Ids of the investments, as listed"),
+    responses(
+        (status = 200, description = "How many were deleted; unknown and already deleted ids aren't counted", body = AffectedRows),
+        (status = 400, description = "An id is malformed", body = Problem),
+    )
+)]
+#[delete("/invs")]
+pub async fn delete_many(ids: web::Json<Vec<String>>) -> Result<Json<AffectedRows>> {
+    let deleted = delete_invs(ids.into_inner()).await?;
+
+    Ok(Json(deleted))
+}
+
+#[utoipa::path(
+    tag = "investments",
+    params(("id" = String, Path, description = "Id of the investment, e.g. `investment:abc`")),
+    responses(
+        (status = 200, description = "The investment, no longer deleted", body = Investment),
+        (status = 404, description = "There is no such investment", body = Problem),
+    )
+)]
+#[post("/inv/{id}/restore")]
+pub async fn restore(id: Path<String>) -> Result<Json<Investment>> {
+    let restored = restore_inv(id.into_inner()).await?;
+
+    Ok(Json(restored))
+}
+
+#[utoipa::path(
+    tag = "investments",
+    params(("id" = String, Path, description = "Id of the investment, e.g. `investment:abc`")),
+    request_body(content = Investment, description = "The investment it is renewed into"),
+    responses(
+        (status = 200, description = "The closed investment and the one renewing it", body = Renewal),
+        (status = 404, description = "There is no such investment", body = Problem),
+        (status = 409, description = "It is already closed or renewed", body = Problem),
+        (status = 422, description = "The new investment failed validation", body = Problem),
+    )
+)]
+#[post("/inv/{id}/renew")]
+pub async fn renew(id: Path<String>, inv: web::Json<Investment>) -> Result<Json<Renewal>> {
+    let renewal = renew_inv(id.into_inner(), inv.into_inner()).await?;
+
+    Ok(Json(renewal))
+}
+
+/// Closes the investment without renewing it; closed and renewed ones can't be.
+#[utoipa::path(
+    tag = "investments",
+    params(("id" = String, Path, description = "Id of the investment, e.g. `investment:abc`")),
+    responses(
+        (status = 200, description = "The investment, now closed", body = Investment),
+        (status = 404, description = "There is no such investment", body = Problem),
+        (status = 409, description = "It is already closed or renewed", body = Problem),
+    )
+)]
+#[post("/inv/{id}/close")]
+pub async fn close(id: Path<String>) -> Result<Json<Investment>> {
+    let closed = close_inv(id.into_inner()).await?;
+
+    Ok(Json(closed))
+}
+
+/// The investments `id` was renewed from and into, itself included, oldest first.
+#[utoipa::path(
+    tag = "investments",
+    params(("id" = String, Path, description = "Id of the investment, e.g. `investment:abc`")),
+    responses(
+        (status = 200, description = "The renewal chain, oldest first", body = Vec<Investment>),
+        (status = 404, description = "There is no such investment", body = Problem),
+    )
+)]
+#[get("/inv/{id}/renewals")]
+pub async fn renewals(id: Path<String>) -> Result<Json<Vec<Investment>>> {
+    let chain = get_renewal_chain(id.into_inner()).await?;
+
+    Ok(Json(chain))
+}
+
+#[utoipa::path(
+    tag = "investments",
+    params(("id" = String, Path, description = "Id of the investment, e.g. `investment:abc`")),
+    responses(
+        (status = 200, description = "The investment, now archived", body = Investment),
+        (status = 404, description = "There is no such investment", body = Problem),
+    )
+)]
+#[post("/inv/{id}/archive")]
+pub async fn archive(id: Path<String>) -> Result<Json<Investment>> {
+    let archived = set_archived(id.into_inner(), true).await?;
+
+    Ok(Json(archived))
+}
+
+/// Body: the ids of the investments to archive, e.g. `["investment:abc"]`.
+#[utoipa::path(
+    tag = "investments",
+    request_body(content = Vec<String>, description = "Ids of the investments, as listed"),
+    responses(
+        (status = 200, description = "How many were archived; unknown, deleted and already archived ids aren't counted", body = AffectedRows),
+        (status = 400, description = "An id is malformed", body = Problem),
+    )
+)]
+#[post("/invs/archive")]
+pub async fn archive_many(ids: web::Json<Vec<String>>) -> Result<Json<AffectedRows>> {
+    let archived = archive_invs(ids.into_inner()).await?;
+
+    Ok(Json(archived))
+}
+
+#[utoipa::path(
+    tag = "investments",
+    params(("id" = String, Path, description = "Id of the investment, e.g. `investment:abc`")),
+    responses(
+        (status = 200, description = "The investment, back among the active ones", body = Investment),
+        (status = 404, description = "There is no such investment", body = Problem),
+    )
+)]
+#[post("/inv/{id}/unarchive")]
+pub async fn unarchive(id: Path<String>) -> Result<Json<Investment>> {
+    let unarchived = set_archived(id.into_inner(), false).await?;
+
+    Ok(Json(unarchived))
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct ReminderRequest {
+    days_before: i64,
+    email: String,
+}
+
+#[utoipa::path(
+    tag = "reminders",
+    params(("id" = String, Path, description = "Id of the investment, e.g. `investment:abc`")),
+    request_body = ReminderRequest,
+    responses(
+        (status = 200, description = "The reminder as stored", body = Reminder),
+        (status = 404, description = "There is no such investment", body = Problem),
+    )
+)]
+#[post("/inv/{id}/reminder")]
+pub async fn reminder(
+    id: Path<String>,
+    body: web::Json<ReminderRequest>,
+) -> Result<Json<Reminder>> {
+    let body = body.into_inner();
+    let created = add_reminder(id.into_inner(), body.days_before, body.email).await?;
+
+    Ok(Json(created))
+}
+
+/// Reminders that are due; meant for an external notifier to poll, then to
+/// mark each one sent once delivered.
+#[utoipa::path(
+    tag = "reminders",
+    responses((status = 200, description = "Unsent reminders whose day has come", body = Vec<Reminder>))
+)]
+#[get("/reminders/due")]
+pub async fn due_reminders() -> Result<Json<Vec<Reminder>>> {
+    let due = get_due_reminders().await?;
+
+    Ok(Json(due))
+}
+
+#[utoipa::path(
+    tag = "reminders",
+    params(("id" = String, Path, description = "Id of the reminder, e.g. `reminder:abc`")),
+    responses(
+        (status = 200, description = "The reminder, with when it was sent", body = Reminder),
+        (status = 400, description = "The id is not a reminder id", body = Problem),
+        (status = 404, description = "There is no such reminder", body = Problem),
+    )
+)]
+#[post("/reminder/{id}/sent")]
+pub async fn reminder_sent(id: Path<String>) -> Result<Json<Reminder>> {
+    let sent = mark_reminder_sent(id.into_inner()).await?;
+
+    Ok(Json(sent))
+}
+
+/// JSON by default, one page at a time; CSV, with every matching row, when the
+/// `Accept` header asks for `text/csv`.
+#[utoipa::path(
+    tag = "investments",
+    params(ListQuery),
+    responses(
+        (status = 200, description = "One page of investments, or all of them as CSV", content(
+            (Vec<Investment> = "application/json"),
+            (String = "text/csv"),
+        )),
+        (status = 304, description = "The page still matches the `If-None-Match` sent"),
+        (status = 400, description = "A filter or the sort is invalid", body = Problem),
+    )
+)]
 #[get("/invs")]
-pub async fn list() -> Result<Json<Vec<Investment>>> {
-    let todos = get_all_invs().await?;
-    Ok(Json(todos))
+pub async fn list(req: HttpRequest, query: web::Query<ListQuery>) -> Result<HttpResponse> {
+    let conversion = Conversion::requested(query.currency.as_deref())?;
+    let accept = req
+        .headers()
+        .get(header::ACCEPT)
+        .and_then(|accept| accept.to_str().ok());
+    if csv::accepted(accept) {
+        return list_csv(&query, conversion).await;
+    }
+
+    let mut todos = get_all_invs(&query).await?;
+    let now = chrono::Utc::now();
+    for inv in &mut todos.investments {
+        inv.fill_status(now);
+    }
+
+    let mut response = list_headers(&todos);
+    if let Some(conversion) = &conversion {
+        conversion.apply(&mut todos.investments);
+        conversion.insert_headers(&mut response);
+    }
+
+    let page = PageInfo::of(&todos);
+    list_json(&req, response, &todos.investments, &query, page)
+}
+
+/// Every row `query` matches as CSV, in its order; a spreadsheet wants the
+/// whole list, so paging parameters are ignored and pages are read until none
+/// are left.
+async fn list_csv(query: &ListQuery, conversion: Option<Conversion>) -> Result<HttpResponse> {
+    let mut query = ListQuery {
+        limit: Some(CONFIG.max_list_rows),
+        offset: 0,
+        page: None,
+        after: None,
+        envelope: false,
+        ..query.clone()
+    };
+    let mut investments = Vec::new();
+    loop {
+        let page = get_all_invs(&query).await?;
+        investments.extend(page.investments);
+        if !page.truncated {
+            break;
+        }
+        query.offset += page.limit;
+    }
+
+    let now = chrono::Utc::now();
+    for inv in &mut investments {
+        inv.fill_status(now);
+    }
+    let mut response = HttpResponse::Ok();
+    response.insert_header((header::VARY, "Accept"));
+    if let Some(conversion) = &conversion {
+        conversion.apply(&mut investments);
+        conversion.insert_headers(&mut response);
+    }
+
+    Ok(response
+        .content_type(csv::CONTENT_TYPE)
+        .insert_header((header::CONTENT_DISPOSITION, csv::CONTENT_DISPOSITION))
+        .body(csv::investments(&investments)))
+}
+
+/// The same list as `/invs` with computed figures; use `/invs` when editing.
+#[utoipa::path(
+    tag = "investments",
+    params(ListQuery),
+    responses(
+        (status = 200, description = "One page of investments with their computed figures", body = Vec<InvestmentView>),
+        (status = 304, description = "The page still matches the `If-None-Match` sent"),
+        (status = 400, description = "A filter or the sort is invalid", body = Problem),
+    )
+)]
+#[get("/invs/view")]
+pub async fn list_view(req: HttpRequest, query: web::Query<ListQuery>) -> Result<HttpResponse> {
+    let now = chrono::Utc::now();
+    let options = CONFIG.calc_options();
+    let listing = get_all_invs(&query).await?;
+    let response = list_headers(&listing);
+    let page = PageInfo::of(&listing);
+    let views: Vec<InvestmentView> = listing
+        .investments
+        .into_iter()
+        .map(|inv| InvestmentView::new(inv, now, &options))
+        .collect();
+
+    list_json(&req, response, &views, &query, page)
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ReorderQuery {
+    /// Rows before the page being reordered; its first id gets `offset + 1`.
+    #[serde(default)]
+    offset: usize,
+}
+
+/// Body: every id on the page in the order the user arranged them.
+#[utoipa::path(
+    tag = "investments",
+    params(ReorderQuery),
+    request_body(content = Vec<String>, description = "Every id on the page, in the new order"),
+    responses((status = 200, description = "How many investments were renumbered", body = AffectedRows))
+)]
+#[patch("/invs/reorder")]
+pub async fn reorder(
+    ids: web::Json<Vec<String>>,
+    query: web::Query<ReorderQuery>,
+) -> Result<Json<AffectedRows>> {
+    let reordered = reorder_invs(ids.into_inner(), query.offset).await?;
+
+    Ok(Json(reordered))
+}
+
+/// Development only: answers 404 unless `DEV_MODE` is set.
+#[utoipa::path(
+    tag = "maintenance",
+    responses(
+        (status = 200, description = "How many sample investments were added", body = AffectedRows),
+        (status = 404, description = "`DEV_MODE` is not set", body = Problem),
+    )
+)]
+#[post("/invs/seed")]
+pub async fn seed() -> Result<Json<AffectedRows>> {
+    if !CONFIG.dev_mode {
+        return Err(Error::NotFound("Not found".into()));
+    }
+
+    Ok(Json(seed_invs().await?))
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct RecomputeQuery {
+    /// Report the differences without writing anything.
+    #[serde(default)]
+    dry_run: bool,
+}
+
+/// Development only: answers 404 unless `DEV_MODE` is set. Rewrites every
+/// `return_amount` that differs from the computed maturity value.
+#[utoipa::path(
+    tag = "maintenance",
+    params(RecomputeQuery),
+    responses(
+        (status = 200, description = "The amounts that differed", body = RecomputeReport),
+        (status = 404, description = "`DEV_MODE` is not set", body = Problem),
+    )
+)]
+#[post("/invs/recompute")]
+pub async fn recompute(query: web::Query<RecomputeQuery>) -> Result<Json<RecomputeReport>> {
+    if !CONFIG.dev_mode {
+        return Err(Error::NotFound("Not found".into()));
+    }
+
+    Ok(Json(recompute_return_amounts(query.dry_run).await?))
+}
+
+/// Every investment, archived ones included, wrapped with the backup format version.
+#[utoipa::path(
+    tag = "maintenance",
+    responses((status = 200, description = "The backup", body = Backup))
+)]
+#[get("/invs/export.json")]
+pub async fn export() -> Result<Json<Backup>> {
+    let investments = get_every_inv().await?;
+
+    Ok(Json(Backup {
+        version: BACKUP_VERSION,
+        investments,
+    }))
+}
+
+/// Restores a backup from `/invs/export.json`, migrating older versions first.
+#[utoipa::path(
+    tag = "maintenance",
+    request_body(content = Backup, description = "A backup of this or an older version"),
+    responses(
+        (status = 200, description = "How many investments were created", body = AffectedRows),
+        (status = 400, description = "The backup can't be read", body = Problem),
+        (status = 422, description = "Some investments failed validation", body = Problem),
+    )
+)]
+#[post("/invs/import")]
+pub async fn import(backup: web::Json<Value>) -> Result<Json<AffectedRows>> {
+    let investments = backup::read(backup.into_inner())?;
+
+    Ok(Json(import_invs(investments).await?))
+}
+
+#[utoipa::path(
+    tag = "investments",
+    responses((status = 200, description = "Every investor name in use, sorted", body = Vec<String>))
+)]
+#[get("/invs/names")]
+pub async fn names() -> Result<Json<Vec<String>>> {
+    let names = get_investor_names().await?;
+
+    Ok(Json(names))
+}
+
+/// Server-sent events, one `data:` line of JSON per created, updated or deleted investment.
+#[utoipa::path(
+    tag = "investments",
+    responses((status = 200, description = "An endless stream of changes", body = String, content_type = "text/event-stream"))
+)]
+#[get("/invs/events")]
+pub async fn events() -> HttpResponse {
+    let stream = futures::stream::unfold(live::subscribe(), |mut changes| async move {
+        loop {
+            match changes.recv().await {
+                Ok(change) => {
+                    let Ok(json) = serde_json::to_string(&change) else {
+                        continue;
+                    };
+                    let event = Bytes::from(format!("data: {json}\n\n"));
+                    return Some((Ok::<_, actix_web::Error>(event), changes));
+                }
+                // the client missed some changes; it still gets the ones after them
+                Err(RecvError::Lagged(_)) => continue,
+                Err(RecvError::Closed) => return None,
+            }
+        }
+    });
+
+    HttpResponse::Ok()
+        .content_type("text/event-stream")
+        .insert_header(("Cache-Control", "no-cache"))
+        .streaming(stream)
+}
+
+/// What `stream` sends after the page in hand.
+enum StreamNext {
+    Page(InvList),
+    After(String),
+    Done,
+}
+
+/// Every investment `/invs` would list, as newline-delimited JSON, oldest
+/// first. Read from the database a page at a time, so memory stays flat
+/// however many there are. Takes the same filters as `/invs` but not paging.
+#[utoipa::path(
+    tag = "investments",
+    params(ListQuery),
+    responses(
+        (status = 200, description = "One investment per line", body = String, content_type = "application/x-ndjson"),
+        (status = 400, description = "A filter is invalid", body = Problem),
+    )
+)]
+#[get("/invs/stream")]
+pub async fn stream(query: web::Query<ListQuery>) -> Result<HttpResponse> {
+    let mut query = query.into_inner();
+    query.after = Some(query.after.unwrap_or_default());
+    query.limit = Some(CONFIG.max_list_rows);
+    query.envelope = false;
+    // fetched before answering, so a bad query still gets an error status
+    let first = get_all_invs(&query).await?;
+
+    let lines = futures::stream::unfold(
+        (query, StreamNext::Page(first)),
+        |(mut query, next)| async move {
+            let mut page = match next {
+                StreamNext::Page(page) => page,
+                StreamNext::After(cursor) => {
+                    query.after = Some(cursor);
+                    match get_all_invs(&query).await {
+                        Ok(page) => page,
+                        // too late for an error status; the client sees the stream end early
+                        Err(err) => {
+                            log::error!("stream stopped: {err}");
+                            return None;
+                        }
+                    }
+                }
+                StreamNext::Done => return None,
+            };
+
+            let now = chrono::Utc::now();
+            let mut chunk = String::new();
+            for inv in &mut page.investments {
+                inv.fill_status(now);
+                if let Ok(json) = serde_json::to_string(inv) {
+                    chunk.push_str(&json);
+                    chunk.push('\n');
+                }
+            }
+            let next = page.next_cursor.map_or(StreamNext::Done, StreamNext::After);
+            Some((Ok::<_, actix_web::Error>(Bytes::from(chunk)), (query, next)))
+        },
+    );
+
+    Ok(HttpResponse::Ok()
+        .content_type("application/x-ndjson")
+        .streaming(lines))
+}
+
+#[utoipa::path(
+    tag = "reports",
+    responses((status = 200, description = "Interest earned per fiscal year", body = Vec<FiscalYearInterest>))
+)]
+#[get("/invs/interest-by-year")]
+pub async fn interest_by_year() -> Result<Json<Vec<FiscalYearInterest>>> {
+    let years = report::interest_by_year().await?;
+
+    Ok(Json(years))
+}
+
+#[utoipa::path(
+    tag = "reports",
+    responses((status = 200, description = "Share of the amount invested per type", body = Vec<AllocationSlice>))
+)]
+#[get("/invs/allocation")]
+pub async fn allocation() -> Result<Json<Vec<AllocationSlice>>> {
+    let slices = report::allocation().await?;
+
+    Ok(Json(slices))
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct SummaryQuery {
+    /// `name` (the investor, the default), `inv_name` or `inv_type`.
+    group_by: Option<String>,
+}
+
+/// Amounts invested and returned per person, investment name or type.
+#[utoipa::path(
+    tag = "reports",
+    params(SummaryQuery),
+    responses(
+        (status = 200, description = "One group per distinct value", body = Vec<SummaryGroup>),
+        (status = 400, description = "It can't be grouped by that", body = Problem),
+    )
+)]
+#[get("/summary")]
+pub async fn summary(query: web::Query<SummaryQuery>) -> Result<Json<Vec<SummaryGroup>>> {
+    let groups = get_summary(query.group_by.as_deref().unwrap_or("name")).await?;
+
+    Ok(Json(groups))
+}
+
+#[utoipa::path(
+    tag = "reports",
+    responses((status = 200, description = "Totals over the active investments", body = Stats))
+)]
+#[get("/invs/stats")]
+pub async fn stats() -> Result<Json<Stats>> {
+    let stats = get_stats().await?;
+
+    Ok(Json(stats))
+}
+
+#[utoipa::path(
+    tag = "reports",
+    responses((status = 200, description = "Portfolio-wide figures", body = Scorecard))
+)]
+#[get("/invs/scorecard")]
+pub async fn scorecard() -> Result<Json<Scorecard>> {
+    let scorecard = report::scorecard().await?;
+
+    Ok(Json(scorecard))
+}
+
+/// The amount-weighted average end date of the investments yet to mature.
+#[utoipa::path(
+    tag = "reports",
+    responses(
+        (status = 200, description = "The date, in RFC 3339", body = String),
+        (status = 404, description = "No investment matures in the future", body = Problem),
+    )
+)]
+#[get("/invs/blended-maturity")]
+pub async fn blended_maturity() -> Result<Json<chrono::DateTime<chrono::Utc>>> {
+    let date = report::blended_maturity().await?.ok_or(Error::NotFound(
+        "No investment matures in the future".into(),
+    ))?;
+
+    Ok(Json(date))
+}
+
+#[utoipa::path(
+    tag = "reports",
+    responses(
+        (status = 200, description = "The investment maturing soonest", body = Investment),
+        (status = 404, description = "No investment matures in the future", body = Problem),
+    )
+)]
+#[get("/invs/next-maturing")]
+pub async fn next_maturing() -> Result<Json<Investment>> {
+    let next = get_next_maturing_inv().await?.ok_or(Error::NotFound(
+        "No investment matures in the future".into(),
+    ))?;
+
+    Ok(Json(next))
+}
+
+/// The options the server computes interest with, so clients can match its figures.
+#[utoipa::path(
+    tag = "reports",
+    responses((status = 200, description = "The rounding and day count in use", body = CalcOptions))
+)]
+#[get("/calc-options")]
+pub async fn calc_options() -> Json<CalcOptions> {
+    Json(CONFIG.calc_options())
+}
+
+/// Header set when more rows follow the page returned, so clients can offer the next one.
+pub const TRUNCATED_HEADER: &str = "X-Result-Truncated";
+
+/// Header carrying the page size a list was cut to, the configured default unless `limit` was sent.
+pub const PAGE_SIZE_HEADER: &str = "X-Page-Size";
+
+/// Header carrying the `after` cursor for the next page, when paging by cursor and more rows follow.
+pub const NEXT_CURSOR_HEADER: &str = "X-Next-Cursor";
+
+/// A list wrapped with its page, for `?envelope=true`.
+#[derive(Serialize)]
+struct Envelope<'a, T> {
+    data: &'a T,
+    page: PageInfo,
+}
+
+#[derive(Serialize)]
+struct PageInfo {
+    limit: usize,
+    offset: usize,
+    /// Rows matching the filters across all pages.
+    total: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    next_cursor: Option<String>,
+}
+
+impl PageInfo {
+    fn of(list: &InvList) -> Self {
+        Self {
+            limit: list.limit,
+            offset: list.offset,
+            total: list.total.unwrap_or_default(),
+            next_cursor: list.next_cursor.clone(),
+        }
+    }
+}
+
+/// The items as a bare JSON array, or in an `Envelope` when the query asks for
+/// one. Tagged with an ETag of the body, and 304 when `If-None-Match` names it.
+fn list_json<T: Serialize>(
+    req: &HttpRequest,
+    mut response: HttpResponseBuilder,
+    items: &T,
+    query: &ListQuery,
+    page: PageInfo,
+) -> Result<HttpResponse> {
+    let body = if query.envelope {
+        serde_json::to_vec(&Envelope { data: items, page })
+    } else {
+        serde_json::to_vec(items)
+    }
+    .map_err(|e| Error::Generic(e.to_string()))?;
+
+    let etag = etag::of_body(&body);
+    response.insert_header((header::ETAG, etag.clone()));
+    if etag::listed(req, header::IF_NONE_MATCH, &etag) == Some(true) {
+        return Ok(response.status(StatusCode::NOT_MODIFIED).finish());
+    }
+
+    Ok(response.content_type("application/json").body(body))
+}
+
+fn list_headers(list: &InvList) -> HttpResponseBuilder {
+    let mut response = HttpResponse::Ok();
+    // the same URL answers in JSON or CSV
+    response.insert_header((header::VARY, "Accept"));
+    response.insert_header((PAGE_SIZE_HEADER, list.limit.to_string()));
+    if list.truncated {
+        response.insert_header((TRUNCATED_HEADER, "true"));
+    }
+    if let Some(cursor) = &list.next_cursor {
+        response.insert_header((NEXT_CURSOR_HEADER, cursor.as_str()));
+    }
+
+    response
 }
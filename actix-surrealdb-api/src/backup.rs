@@ -0,0 +1,124 @@
+//! Reading backups written by `GET /invs/export.json`, whatever their version.
+//!
+//! A format change bumps `BACKUP_VERSION` and adds a step to `migrate` that
+//! turns the previous version's JSON into the next one, so every older
+//! backup still imports.
+
+use serde_json::Value;
+
+use crate::prelude::*;
+use types::validation::{self, FieldError};
+use types::{Backup, Investment, BACKUP_VERSION};
+
+/// The investments in `backup`, brought up to the current format and checked
+/// all at once. Dates may be missing, since the export keeps records saved
+/// before they were required; any other broken rule is reported against
+/// `investments[index]`.
+pub fn read(backup: Value) -> Result<Vec<Investment>> {
+    let version = backup
+        .get("version")
+        .and_then(Value::as_u64)
+        .ok_or(Error::BadRequest("Backup has no version".into()))?;
+    let current = migrate(version, backup)?;
+    let backup: Backup = serde_json::from_value(current)
+        .map_err(|e| Error::BadRequest(format!("Invalid backup: {e}")))?;
+
+    let errors: Vec<FieldError> = backup
+        .investments
+        .iter()
+        .enumerate()
+        .flat_map(|(index, inv)| {
+            validation::validate_existing(inv)
+                .into_iter()
+                .map(move |error| FieldError {
+                    field: format!("investments[{index}].{}", error.field),
+                    message: error.message,
+                })
+        })
+        .collect();
+    if !errors.is_empty() {
+        return Err(Error::Validation(errors));
+    }
+
+    Ok(backup.investments)
+}
+
+/// Applies the migration steps from `version` up to `BACKUP_VERSION`.
+fn migrate(version: u64, backup: Value) -> Result<Value> {
+    match version {
+        // the current format, nothing to do
+        1 => Ok(backup),
+        _ => Err(Error::BadRequest(format!(
+            "Unsupported backup version {version}; this server reads versions 1 to {BACKUP_VERSION}"
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{TimeZone, Utc};
+    use serde_json::json;
+
+    use super::*;
+
+    fn fd() -> Investment {
+        Investment {
+            inv_name: "SBI".into(),
+            inv_type: "FD".into(),
+            return_rate: 700,
+            return_type: "Ordinary".into(),
+            inv_amount: 100_000,
+            return_amount: 121_000,
+            name: "Asha".into(),
+            start_date: Utc.with_ymd_and_hms(2023, 4, 1, 0, 0, 0).single(),
+            end_date: Utc.with_ymd_and_hms(2026, 4, 1, 0, 0, 0).single(),
+            ..Default::default()
+        }
+    }
+
+    fn backup(version: u64, investments: &[Investment]) -> Value {
+        json!({ "version": version, "investments": investments })
+    }
+
+    #[test]
+    fn an_export_reads_back_unchanged() {
+        let investments = vec![fd()];
+
+        assert_eq!(read(backup(1, &investments)).unwrap(), investments);
+    }
+
+    #[test]
+    fn an_undated_record_is_accepted() {
+        let undated = Investment {
+            start_date: None,
+            end_date: None,
+            ..fd()
+        };
+
+        assert_eq!(read(backup(1, &[undated.clone()])).unwrap(), [undated]);
+    }
+
+    #[test]
+    fn a_broken_record_is_reported_by_its_index() {
+        let blank = Investment {
+            name: " ".into(),
+            ..fd()
+        };
+
+        match read(backup(1, &[fd(), blank])) {
+            Err(Error::Validation(errors)) => {
+                assert_eq!(errors.len(), 1);
+                assert_eq!(errors[0].field, "investments[1].name");
+            }
+            other => panic!("expected a validation error, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn an_unknown_version_is_rejected() {
+        assert!(matches!(
+            read(backup(999, &[fd()])),
+            Err(Error::BadRequest(_))
+        ));
+    }
+}
@@ -0,0 +1,61 @@
+//! In-memory cache for derived query results.
+//!
+//! Entries expire after `CONFIG.cache_ttl` and the whole cache is dropped on
+//! every create/update/delete, so a cached value never outlives a mutation.
+
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::Instant;
+
+use once_cell::sync::Lazy;
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::Value;
+
+use crate::config::CONFIG;
+use crate::prelude::*;
+
+static CACHE: Lazy<Mutex<HashMap<String, (Instant, Value)>>> = Lazy::new(Default::default);
+
+/// Bumped on every invalidation so a load that raced with a mutation isn't stored.
+static GENERATION: AtomicU64 = AtomicU64::new(0);
+
+/// Returns the cached value for `key` while fresh, otherwise awaits `load` and caches it.
+pub async fn cached<T, F>(key: &str, load: F) -> Result<T>
+where
+    T: Serialize + DeserializeOwned,
+    F: Future<Output = Result<T>>,
+{
+    if let Some(value) = fresh(key) {
+        if let Ok(value) = serde_json::from_value(value) {
+            return Ok(value);
+        }
+    }
+
+    let generation = GENERATION.load(Ordering::SeqCst);
+    let value = load.await?;
+
+    if let (Ok(json), Ok(mut cache)) = (serde_json::to_value(&value), CACHE.lock()) {
+        if generation == GENERATION.load(Ordering::SeqCst) {
+            cache.insert(key.to_string(), (Instant::now(), json));
+        }
+    }
+
+    Ok(value)
+}
+
+/// Drops every cached entry. Called by the db layer after each mutation.
+pub fn invalidate() {
+    GENERATION.fetch_add(1, Ordering::SeqCst);
+    if let Ok(mut cache) = CACHE.lock() {
+        cache.clear();
+    }
+}
+
+fn fresh(key: &str) -> Option<Value> {
+    let cache = CACHE.lock().ok()?;
+    let (stored_at, value) = cache.get(key)?;
+
+    (stored_at.elapsed() < CONFIG.cache_ttl).then(|| value.clone())
+}
@@ -0,0 +1,222 @@
+//! Runtime configuration, read once from the environment.
+
+use std::collections::BTreeMap;
+use std::str::FromStr;
+use std::time::Duration;
+
+use once_cell::sync::Lazy;
+use types::interest::{CalcOptions, DayCount, RoundingMode};
+
+pub static CONFIG: Lazy<Config> = Lazy::new(Config::from_env);
+
+pub struct Config {
+    /// Host the HTTP server binds to, from `HOST`.
+    pub host: String,
+    /// Port the HTTP server binds to, from `PORT`.
+    pub port: u16,
+    /// SurrealDB address (host:port) reached over WebSocket, from `SURREAL_URL`.
+    pub db_url: String,
+    /// From `SURREAL_NS`.
+    pub db_namespace: String,
+    /// From `SURREAL_DB`.
+    pub db_database: String,
+    /// Root user, from `SURREAL_USER`.
+    pub db_username: String,
+    /// Root password, from `SURREAL_PASS`. Never logged.
+    pub db_password: String,
+    /// How long cached aggregate results are served before being recomputed.
+    pub cache_ttl: Duration,
+    /// How interest and maturity figures are rounded, from `ROUNDING_MODE` (half-up, floor or ceil).
+    pub rounding: RoundingMode,
+    /// How partial years are counted, from `DAY_COUNT` (actual-365, actual-360 or 30-360).
+    pub day_count: DayCount,
+    /// Hard cap on rows returned by one list request, from `MAX_LIST_ROWS`.
+    pub max_list_rows: usize,
+    /// Rows per list page when the client doesn't ask for a `limit`, from `DEFAULT_PAGE_SIZE`.
+    pub default_page_size: usize,
+    /// Enables development-only endpoints such as seeding, from `DEV_MODE`.
+    pub dev_mode: bool,
+    /// Fixed rates lists can be converted at, from `CURRENCY_RATES`, e.g. `USD=0.012,EUR=0.011`.
+    pub currency_rates: CurrencyRates,
+    /// Origins browsers may call from, from `CORS_ORIGINS`, e.g. `https://money.example`.
+    pub cors_origins: AllowList,
+    /// Methods allowed cross-origin, from `CORS_METHODS`.
+    pub cors_methods: AllowList,
+    /// Request headers allowed cross-origin, from `CORS_HEADERS`.
+    pub cors_headers: AllowList,
+    /// How long browsers may cache a preflight answer, from `CORS_MAX_AGE_SECS`.
+    pub cors_max_age: usize,
+}
+
+/// A comma-separated list of allowed values; `*` alone allows any.
+#[derive(Clone, Debug)]
+pub enum AllowList {
+    Any,
+    Only(Vec<String>),
+}
+
+impl AllowList {
+    fn only(values: &[&str]) -> Self {
+        Self::Only(values.iter().map(ToString::to_string).collect())
+    }
+
+    fn describe(&self) -> String {
+        match self {
+            AllowList::Any => "*".to_string(),
+            AllowList::Only(values) => values.join("|"),
+        }
+    }
+}
+
+impl FromStr for AllowList {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.trim() == "*" {
+            return Ok(Self::Any);
+        }
+        let values: Vec<String> = s
+            .split(',')
+            .map(str::trim)
+            .filter(|value| !value.is_empty())
+            .map(ToString::to_string)
+            .collect();
+        if values.is_empty() {
+            return Err("expected * or a comma-separated list".to_string());
+        }
+
+        Ok(Self::Only(values))
+    }
+}
+
+/// Units of each currency one rupee buys, keyed by upper-case ISO code.
+#[derive(Debug, Default)]
+pub struct CurrencyRates(BTreeMap<String, f64>);
+
+impl CurrencyRates {
+    pub fn get(&self, code: &str) -> Option<f64> {
+        self.0.get(&code.to_ascii_uppercase()).copied()
+    }
+
+    fn codes(&self) -> String {
+        self.0.keys().cloned().collect::<Vec<_>>().join("|")
+    }
+}
+
+impl FromStr for CurrencyRates {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut rates = BTreeMap::new();
+        for pair in s.split(',').map(str::trim).filter(|pair| !pair.is_empty()) {
+            let (code, rate) = pair
+                .split_once('=')
+                .ok_or_else(|| format!("expected CODE=rate, got {pair}"))?;
+            let rate: f64 = rate
+                .trim()
+                .parse()
+                .map_err(|_| format!("invalid rate for {code}: {rate}"))?;
+            if !(rate.is_finite() && rate > 0.0) {
+                return Err(format!("rate for {code} must be positive"));
+            }
+            rates.insert(code.trim().to_ascii_uppercase(), rate);
+        }
+
+        Ok(Self(rates))
+    }
+}
+
+impl Config {
+    fn from_env() -> Self {
+        Self {
+            host: env_or("HOST", "localhost".to_string()),
+            port: env_or("PORT", 8080),
+            db_url: env_or("SURREAL_URL", "localhost:8000".to_string()),
+            db_namespace: env_or("SURREAL_NS", "namespace".to_string()),
+            db_database: env_or("SURREAL_DB", "database".to_string()),
+            db_username: env_or("SURREAL_USER", "root".to_string()),
+            db_password: env_or("SURREAL_PASS", "root".to_string()),
+            cache_ttl: Duration::from_secs(env_or("CACHE_TTL_SECS", 30)),
+            rounding: env_or("ROUNDING_MODE", RoundingMode::default()),
+            day_count: env_or("DAY_COUNT", DayCount::default()),
+            max_list_rows: env_or("MAX_LIST_ROWS", 1000),
+            default_page_size: env_or("DEFAULT_PAGE_SIZE", 25),
+            dev_mode: env_or("DEV_MODE", false),
+            currency_rates: env_or("CURRENCY_RATES", CurrencyRates::default()),
+            cors_origins: env_or("CORS_ORIGINS", AllowList::Any),
+            cors_methods: env_or(
+                "CORS_METHODS",
+                AllowList::only(&["GET", "POST", "PATCH", "DELETE"]),
+            ),
+            cors_headers: env_or("CORS_HEADERS", AllowList::Any),
+            cors_max_age: env_or("CORS_MAX_AGE_SECS", 3600),
+        }
+    }
+
+    /// Every setting on one line, with the database password masked.
+    pub fn summary(&self) -> String {
+        format!(
+            "bind={}:{} surreal=ws://{} ns={} db={} user={} password=*** cache_ttl={}s rounding={:?} day_count={:?} max_list_rows={} default_page_size={} dev_mode={} currencies={} cors_origins={} cors_methods={} cors_headers={} cors_max_age={}s",
+            self.host,
+            self.port,
+            self.db_url,
+            self.db_namespace,
+            self.db_database,
+            self.db_username,
+            self.cache_ttl.as_secs(),
+            self.rounding,
+            self.day_count,
+            self.max_list_rows,
+            self.default_page_size,
+            self.dev_mode,
+            self.currency_rates.codes(),
+            self.cors_origins.describe(),
+            self.cors_methods.describe(),
+            self.cors_headers.describe(),
+            self.cors_max_age,
+        )
+    }
+
+    pub fn calc_options(&self) -> CalcOptions {
+        CalcOptions {
+            rounding: self.rounding,
+            day_count: self.day_count,
+        }
+    }
+}
+
+/// Reads and parses an environment variable, falling back to `default` when
+/// unset or invalid. An invalid value is logged, so a typo doesn't go unnoticed.
+fn env_or<T: FromStr>(key: &str, default: T) -> T {
+    let Ok(value) = std::env::var(key) else {
+        return default;
+    };
+
+    value.parse().unwrap_or_else(|_| {
+        log::warn!("Ignoring {key}={value:?}: not a valid value; using the default");
+        default
+    })
+}
+
+/// Logs the configuration the server actually runs with, so a bad deployment
+/// shows up in the first lines of output.
+pub fn log_effective_config() {
+    log::info!("Effective configuration: {}", CONFIG.summary());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn summary_masks_the_password() {
+        let config = Config {
+            db_password: "s3cret-pass".to_string(),
+            ..Config::from_env()
+        };
+        let summary = config.summary();
+
+        assert!(!summary.contains("s3cret-pass"));
+        assert!(summary.contains("password=***"));
+    }
+}
@@ -0,0 +1,144 @@
+//! CSV rendering of investment lists, for spreadsheets.
+
+use types::Investment;
+
+pub const CONTENT_TYPE: &str = "text/csv; charset=utf-8";
+
+/// Saves the download under a sensible name rather than `invs`.
+pub const CONTENT_DISPOSITION: &str = "attachment; filename=\"investments.csv\"";
+
+const HEADER: [&str; 14] = [
+    "id",
+    "inv_name",
+    "inv_type",
+    "return_rate",
+    "return_type",
+    "inv_amount",
+    "return_amount",
+    "name",
+    "status",
+    "start_date",
+    "end_date",
+    "created_at",
+    "updated_at",
+    "archived",
+];
+
+/// One header line, then one line per investment. Rates are percentages and
+/// dates RFC 3339, the same as in the JSON.
+pub fn investments(investments: &[Investment]) -> String {
+    let mut csv = HEADER.join(",");
+    csv.push_str("\r\n");
+
+    let now = chrono::Utc::now();
+    for inv in investments {
+        let date = |date: Option<chrono::DateTime<chrono::Utc>>| {
+            date.map(|date| date.to_rfc3339()).unwrap_or_default()
+        };
+        let row = [
+            inv.id.as_ref().map(ToString::to_string).unwrap_or_default(),
+            text(&inv.inv_name),
+            text(&inv.inv_type),
+            (inv.return_rate as f64 / 100.0).to_string(),
+            text(&inv.return_type),
+            inv.inv_amount.to_string(),
+            inv.return_amount.to_string(),
+            text(&inv.name),
+            inv.status(now).to_string(),
+            date(inv.start_date),
+            date(inv.end_date),
+            date(inv.created_at),
+            date(inv.updated_at),
+            inv.archived.to_string(),
+        ];
+        let row: Vec<String> = row.iter().map(|field| escape(field)).collect();
+        csv.push_str(&row.join(","));
+        csv.push_str("\r\n");
+    }
+
+    csv
+}
+
+/// A free-text field, with a leading `'` when it would otherwise start like a
+/// formula; spreadsheets evaluate `=`, `+`, `-` and `@` on import, and some
+/// skip a leading tab or carriage return before looking.
+fn text(field: &str) -> String {
+    if field.starts_with(['=', '+', '-', '@', '\t', '\r']) {
+        format!("'{field}")
+    } else {
+        field.to_string()
+    }
+}
+
+/// Quotes a field when it holds a comma, quote or line break, doubling inner quotes.
+fn escape(field: &str) -> String {
+    if field.contains([',', '"', '\r', '\n']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+/// True when the `Accept` header prefers CSV over JSON. Anything else,
+/// including no header at all, gets JSON.
+pub fn accepted(accept: Option<&str>) -> bool {
+    let Some(accept) = accept else {
+        return false;
+    };
+
+    // the higher quality wins, and on a tie the one listed first
+    let mut best: Option<(f32, bool)> = None;
+    for media_type in accept.split(',') {
+        let mut parts = media_type.split(';');
+        let is_csv = match parts.next().unwrap_or("").trim() {
+            "text/csv" => true,
+            "application/json" => false,
+            _ => continue,
+        };
+        let quality = parts
+            .find_map(|param| param.trim().strip_prefix("q="))
+            .and_then(|quality| quality.trim().parse().ok())
+            .unwrap_or(1.0);
+        if quality > 0.0 && best.map_or(true, |(best, _)| quality > best) {
+            best = Some((quality, is_csv));
+        }
+    }
+
+    best.map_or(false, |(_, is_csv)| is_csv)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn csv_only_when_preferred() {
+        assert!(!accepted(None));
+        assert!(!accepted(Some("*/*")));
+        assert!(accepted(Some("text/csv")));
+        assert!(accepted(Some("application/json;q=0.5, text/csv")));
+        assert!(!accepted(Some("text/csv;q=0.5, application/json;q=0.9")));
+        // a tie goes to the one listed first
+        assert!(!accepted(Some("application/json, text/csv")));
+        assert!(!accepted(Some("text/csv;q=0")));
+    }
+
+    #[test]
+    fn formulas_are_defused() {
+        assert_eq!(text("=SUM(A1:A9)"), "'=SUM(A1:A9)");
+        assert_eq!(text("+91"), "'+91");
+        assert_eq!(text("-1"), "'-1");
+        assert_eq!(text("@cmd"), "'@cmd");
+        assert_eq!(text("\t=1+2"), "'\t=1+2");
+        assert_eq!(text("\r=1+2"), "'\r=1+2");
+        assert_eq!(text("SBI Tax Saver"), "SBI Tax Saver");
+    }
+
+    #[test]
+    fn fields_are_quoted_when_needed() {
+        assert_eq!(escape("plain"), "plain");
+        assert_eq!(escape("a,b"), "\"a,b\"");
+        assert_eq!(escape("say \"hi\""), "\"say \"\"hi\"\"\"");
+        assert_eq!(escape("two\nlines"), "\"two\nlines\"");
+    }
+}
@@ -0,0 +1,112 @@
+//! Lists shown in another currency, at the fixed rates in `CONFIG.currency_rates`.
+
+use actix_web::HttpResponseBuilder;
+
+use crate::config::CONFIG;
+use crate::prelude::*;
+use types::Investment;
+
+/// Header naming the currency a list's amounts were converted to.
+pub const CURRENCY_HEADER: &str = "X-Currency";
+
+/// Header carrying the rate used: units of that currency per rupee.
+pub const RATE_HEADER: &str = "X-Exchange-Rate";
+
+pub struct Conversion {
+    currency: String,
+    rate: f64,
+}
+
+impl Conversion {
+    /// The conversion asked for by `?currency=`; `None` keeps rupees.
+    pub fn requested(currency: Option<&str>) -> Result<Option<Self>> {
+        let Some(currency) = currency else {
+            return Ok(None);
+        };
+        let rate = CONFIG
+            .currency_rates
+            .get(currency)
+            .ok_or_else(|| Error::BadRequest(format!("Unknown currency: {currency}")))?;
+
+        Ok(Some(Self {
+            currency: currency.to_ascii_uppercase(),
+            rate,
+        }))
+    }
+
+    /// Converts every amount on `investments`; rates and dates are left alone.
+    pub fn apply(&self, investments: &mut [Investment]) {
+        for inv in investments {
+            inv.inv_amount = self.convert(inv.inv_amount);
+            inv.return_amount = self.convert(inv.return_amount);
+            inv.monthly_amount = inv.monthly_amount.map(|amount| self.convert(amount));
+        }
+    }
+
+    pub fn insert_headers(&self, response: &mut HttpResponseBuilder) {
+        response.insert_header((CURRENCY_HEADER, self.currency.clone()));
+        response.insert_header((RATE_HEADER, self.rate.to_string()));
+    }
+
+    fn convert(&self, amount: i64) -> i64 {
+        CONFIG.rounding.apply(amount as f64 * self.rate)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::http::header::HeaderValue;
+    use actix_web::HttpResponse;
+
+    use super::*;
+
+    fn usd() -> Conversion {
+        Conversion {
+            currency: "USD".into(),
+            rate: 0.012,
+        }
+    }
+
+    #[test]
+    fn every_amount_is_converted_and_nothing_else() {
+        let mut investments = [Investment {
+            inv_amount: 100_000,
+            return_amount: 121_000,
+            monthly_amount: Some(10_000),
+            return_rate: 700,
+            ..Default::default()
+        }];
+
+        usd().apply(&mut investments);
+
+        assert_eq!(investments[0].inv_amount, 1_200);
+        assert_eq!(investments[0].return_amount, 1_452);
+        assert_eq!(investments[0].monthly_amount, Some(120));
+        assert_eq!(investments[0].return_rate, 700);
+    }
+
+    #[test]
+    fn the_headers_name_the_currency_and_rate() {
+        let mut response = HttpResponse::Ok();
+        usd().insert_headers(&mut response);
+        let response = response.finish();
+
+        assert_eq!(
+            response.headers().get(CURRENCY_HEADER),
+            Some(&HeaderValue::from_static("USD"))
+        );
+        assert_eq!(
+            response.headers().get(RATE_HEADER),
+            Some(&HeaderValue::from_static("0.012"))
+        );
+    }
+
+    #[test]
+    fn rupees_need_no_conversion_and_an_unknown_currency_is_refused() {
+        assert!(matches!(Conversion::requested(None), Ok(None)));
+        assert!(matches!(
+            Conversion::requested(Some("XYZ")),
+            Err(Error::BadRequest(_))
+        ));
+    }
+}
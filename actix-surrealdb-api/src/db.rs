@@ -1,33 +1,179 @@
-use chrono::offset::Utc;
-use surrealdb::sql::Thing;
+use chrono::offset::{TimeZone, Utc};
+use std::collections::BTreeMap;
 
+use chrono::{DateTime, NaiveDate};
+use serde::Deserialize;
+use serde_json::Value;
+use surrealdb::sql::{Id, Thing};
+use utoipa::IntoParams;
+
+use crate::cache;
+use crate::config::CONFIG;
 use crate::prelude::*;
 use crate::DB;
+use types::validation;
 use types::*;
 
-const INVESTMENT: &str = "investment";
+pub const INVESTMENT: &str = "investment";
+pub const AUDIT: &str = "investment_audit";
+pub const REMINDER: &str = "reminder";
+
+/// Query string accepted by `GET /invs`. Filters left out are ignored; the
+/// rest must all match.
+#[derive(Clone, Debug, Default, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ListQuery {
+    /// List archived investments instead of the active ones.
+    #[serde(default)]
+    pub archived: bool,
+    /// Only investments of this type, e.g. `RD`.
+    pub inv_type: Option<String>,
+    /// Only investments maturing before this date (`YYYY-MM-DD`).
+    #[serde(alias = "end_before")]
+    pub maturing_before: Option<String>,
+    /// Only investments maturing on or after this date (`YYYY-MM-DD`).
+    pub maturing_after: Option<String>,
+    /// Only investments starting on or after this date (`YYYY-MM-DD`).
+    pub start_after: Option<String>,
+    /// Only investments of at least this amount invested.
+    pub min_amount: Option<i64>,
+    /// Only investments of at most this amount invested.
+    pub max_amount: Option<i64>,
+    /// Only investments with at least this annual rate, as a percentage.
+    pub min_rate: Option<f64>,
+    /// Rows per page; `CONFIG.default_page_size` when left out, never more than `CONFIG.max_list_rows`.
+    #[serde(alias = "per_page")]
+    pub limit: Option<usize>,
+    /// Rows to skip before the page starts.
+    #[serde(default)]
+    pub offset: usize,
+    /// Page number counting from 1, instead of `offset`: page 3 starts `2 * limit` rows in.
+    pub page: Option<usize>,
+    /// Pages by cursor instead, oldest first: the rows created after the one the
+    /// cursor names. Send it empty for the first page, then the `next_cursor` given.
+    pub after: Option<String>,
+    /// Only these investments, comma-separated, e.g. `investment:a,investment:b`.
+    /// Archived ones are included when asked for by id.
+    pub ids: Option<String>,
+    /// List deleted investments too.
+    #[serde(default)]
+    pub include_deleted: bool,
+    /// Only investments in this state: `active`, `matured`, `closed` or `renewed`.
+    pub status: Option<String>,
+    /// Comma-separated fields to order by, `-` in front for descending, e.g.
+    /// `-end_date,inv_amount`. The manual order, newest first, when left out.
+    pub sort: Option<String>,
+    /// Show amounts in this currency instead of rupees, e.g. `USD`. Only `GET /invs` converts.
+    pub currency: Option<String>,
+    /// Wrap the JSON list with its page details, total included, instead of a bare array.
+    #[serde(default)]
+    pub envelope: bool,
+}
 
+/// Creates an investment. When the client supplies an id the create is
+/// idempotent: retrying with the same id returns the stored record untouched,
+/// unless it has since been deleted, which is a conflict; see `restore_inv`.
 pub async fn add_inv(inv: &mut Investment) -> Result<Investment> {
-    inv.id = None;
+    clear_server_owned(inv);
+    validate_inv(inv)?;
+    let key = inv.id.take().map(|id| client_key(&id)).transpose()?;
     inv.created_at = Some(Utc::now());
     inv.updated_at = Some(Utc::now());
-    let created: Vec<Investment> = DB.create(INVESTMENT).content(inv).await?;
 
-    Ok(created.clone().pop().unwrap())
+    let Some(key) = key else {
+        let created: Vec<Investment> = DB.create(INVESTMENT).content(inv).await?;
+        cache::invalidate();
+
+        return created
+            .into_iter()
+            .next()
+            .ok_or(Error::Generic("Failed to create record".into()));
+    };
+
+    let existing: Option<Investment> = DB.select((INVESTMENT, key.as_str())).await?;
+    if let Some(existing) = existing {
+        return not_deleted(existing);
+    }
+    let created: surrealdb::Result<Option<Investment>> =
+        DB.create((INVESTMENT, key.as_str())).content(inv).await;
+    match created {
+        Ok(created) => {
+            cache::invalidate();
+            created.ok_or(Error::Generic("Failed to create record".into()))
+        }
+        // a concurrent retry may have created it between the select and the create
+        Err(err) => match DB.select((INVESTMENT, key.as_str())).await? {
+            Some(existing) => not_deleted(existing),
+            None => Err(err.into()),
+        },
+    }
 }
 
+/// The record a retried create found, unless it was deleted since: handing
+/// that back would look like a fresh create of something no list shows.
+fn not_deleted(existing: Investment) -> Result<Investment> {
+    if existing.deleted_at.is_none() {
+        return Ok(existing);
+    }
+    let id = existing
+        .id
+        .as_ref()
+        .map(ToString::to_string)
+        .unwrap_or_default();
+
+    Err(Error::Conflict(format!(
+        "Investment {id} was deleted; restore it or create it under another id"
+    )))
+}
+
+/// A deleted investment is not found, as if it were gone for good; see `restore_inv`.
 pub async fn get_inv(id: String) -> Result<Investment> {
-    let th = id.split_once(':').unwrap();
+    let th = id
+        .split_once(':')
+        .ok_or(Error::BadRequest(format!("Invalid investment id: {id}")))?;
     let rec: Option<Investment> = DB.select(th).await?;
 
-    Ok(rec.unwrap())
+    rec.filter(|inv| inv.deleted_at.is_none())
+        .ok_or(Error::NotFound(format!("Investment {id} not found")))
 }
 
-pub async fn delete_inv(id: Thing) -> Result<Record> {
-    let response_option: Option<Record> = DB.delete(id).await?;
-    let response = response_option.ok_or(Error::Generic("Failed to delete record".into()))?;
+/// Marks the investment deleted rather than removing it, so it can be restored.
+/// Not found when there is no such investment or it was deleted already.
+pub async fn delete_inv(id: Thing) -> Result<AffectedRows> {
+    // UPDATE would create a missing record, so only ever mark one that exists
+    let existing: Option<Investment> = DB.select(id.clone()).await?;
+    let Some(previous) = existing.filter(|inv| inv.deleted_at.is_none()) else {
+        return Err(Error::NotFound(format!("Investment {id} not found")));
+    };
+    let sql = "UPDATE $id SET deleted_at = $now;";
+    DB.query(sql)
+        .bind(("id", id))
+        .bind(("now", Utc::now()))
+        .await?;
+    cache::invalidate();
+    record_audit("delete", previous).await;
 
-    Ok(response)
+    Ok(AffectedRows { rows_affected: 1 })
+}
+
+/// Undoes `delete_inv`. Restoring an investment that isn't deleted changes nothing.
+pub async fn restore_inv(id: String) -> Result<Investment> {
+    let (table, key) = id
+        .split_once(':')
+        .ok_or(Error::BadRequest(format!("Invalid investment id: {id}")))?;
+    let thing = Thing::from((table, key));
+    let existing: Option<Investment> = DB.select(thing.clone()).await?;
+    let existing = existing.ok_or(Error::NotFound(format!("Investment {id} not found")))?;
+    if existing.deleted_at.is_none() {
+        return Ok(existing);
+    }
+
+    let sql = "UPDATE $id SET deleted_at = NONE RETURN AFTER;";
+    let mut response = DB.query(sql).bind(("id", thing)).await?;
+    cache::invalidate();
+    let restored: Option<Investment> = response.take(0)?;
+
+    restored.ok_or(Error::Generic("Failed to restore record".into()))
 }
 
 pub async fn update_inv(inv: &mut Investment) -> Result<Investment> {
@@ -35,23 +181,1343 @@ pub async fn update_inv(inv: &mut Investment) -> Result<Investment> {
         Some(thing) => thing,
         None => return Err(Error::Generic("Failed to update record".into())),
     };
+    // UPDATE on a missing id would create it, so an update never gets that far
+    let previous: Option<Investment> = DB.select(thing.clone()).await?;
+    let previous = previous.ok_or(Error::NotFound(format!("Investment {thing} not found")))?;
+    let errors = validation::validate_update(inv, &previous);
+    if !errors.is_empty() {
+        return Err(Error::Validation(errors));
+    }
     let response_option: Option<Investment> = DB.update(thing).content(inv).await?;
+    cache::invalidate();
     let response = response_option.ok_or(Error::Generic("Failed to update record".into()))?;
+    record_audit("update", previous).await;
 
     Ok(response)
 }
 
-pub async fn get_all_invs() -> Result<Vec<Investment>> {
+/// `PATCH /inv`: like `patch_inv`, with the investment's `id` in the body.
+pub async fn update_inv_fields(patch: Value) -> Result<Investment> {
+    let Value::Object(mut fields) = patch else {
+        return Err(Error::BadRequest("Patch must be a JSON object".into()));
+    };
+    let id = fields
+        .remove("id")
+        .ok_or(Error::BadRequest("Missing investment id".into()))?;
+    let id: Thing = serde_json::from_value(id)
+        .map_err(|_| Error::BadRequest("Invalid investment id".into()))?;
+
+    merge_inv(id, fields).await
+}
+
+/// `PATCH /inv/{id}`: merges only the fields present in `patch` into the stored investment.
+pub async fn patch_inv(id: String, patch: Value) -> Result<Investment> {
+    let (table, key) = id
+        .split_once(':')
+        .ok_or(Error::BadRequest(format!("Invalid investment id: {id}")))?;
+    let Value::Object(mut fields) = patch else {
+        return Err(Error::BadRequest("Patch must be a JSON object".into()));
+    };
+    // the id comes from the path, never from the body
+    fields.remove("id");
+
+    merge_inv(Thing::from((table, key)), fields).await
+}
+
+/// Every key a client may patch; a patch with any other key is rejected.
+const INVESTMENT_FIELDS: [&str; 19] = [
+    "id",
+    "inv_name",
+    "inv_type",
+    "return_rate",
+    "return_type",
+    "inv_amount",
+    "return_amount",
+    "name",
+    "inv_status",
+    "start_date",
+    "end_date",
+    "created_at",
+    "updated_at",
+    "archived",
+    "sort_order",
+    "monthly_amount",
+    "installments",
+    "lock_in_days",
+    "status",
+];
+
+/// Keys on the wire that only the server writes: the links a renewal makes.
+const SERVER_OWNED_FIELDS: [&str; 2] = ["renewed_from", "renewed_to"];
+
+/// Merges `fields` into the stored investment with `merge_patch`, then
+/// validates and saves the result through `update_inv`. Fields left out keep
+/// their stored value and `null` clears a field that may be empty. The id and
+/// timestamps stay the server's, as do the renewal links.
+async fn merge_inv(id: Thing, fields: serde_json::Map<String, Value>) -> Result<Investment> {
+    if let Some(field) = SERVER_OWNED_FIELDS
+        .into_iter()
+        .find(|field| fields.contains_key(*field))
+    {
+        return Err(Error::Invalid {
+            field,
+            message: "is set by the server when renewing".to_string(),
+        });
+    }
+    if let Some(unknown) = fields
+        .keys()
+        .find(|key| !INVESTMENT_FIELDS.contains(&key.as_str()))
+    {
+        return Err(Error::BadRequest(format!("Unknown field: {unknown}")));
+    }
+    let stored = get_inv(id.to_string()).await?;
+    check_version(&stored, &fields)?;
+
+    let mut merged = serde_json::to_value(&stored).map_err(|e| Error::Generic(e.to_string()))?;
+    merge_patch(&mut merged, Value::Object(fields));
+    if let Some(merged) = merged.as_object_mut() {
+        merged.insert("id".into(), Value::Null);
+    }
+    let mut inv: Investment = serde_json::from_value(merged)
+        .map_err(|e| Error::BadRequest(format!("Invalid patch: {e}")))?;
+    inv.id = Some(id);
+    inv.created_at = stored.created_at;
+    inv.renewed_from = stored.renewed_from.clone();
+    inv.renewed_to = stored.renewed_to.clone();
+    let now = Utc::now();
+    inv.updated_at = Some(now);
+    if inv.status(now) != stored.status(now) {
+        check_transition(&stored, inv.status(now))?;
+    }
+
+    update_inv(&mut inv).await
+}
+
+/// `updated_at` doubles as the record's version: a client that sends the one
+/// it loaded gets a conflict, rather than overwriting, when the investment has
+/// been saved since. Leaving it out skips the check.
+fn check_version(stored: &Investment, fields: &serde_json::Map<String, Value>) -> Result<()> {
+    let Some(seen) = fields.get("updated_at") else {
+        return Ok(());
+    };
+    let seen: Option<DateTime<Utc>> = serde_json::from_value(seen.clone())
+        .map_err(|_| Error::BadRequest("Invalid updated_at".into()))?;
+    if seen != stored.updated_at {
+        let id = stored
+            .id
+            .as_ref()
+            .map(ToString::to_string)
+            .unwrap_or_default();
+        return Err(Error::Conflict(format!(
+            "Investment {id} was changed since it was loaded; reload it and try again"
+        )));
+    }
+
+    Ok(())
+}
+
+/// Applies `patch` to `target` as RFC 7396 says: objects merge key by key, so
+/// a nested `inv_status` can be patched in part, `null` removes a key and
+/// anything else replaces the value outright.
+fn merge_patch(target: &mut Value, patch: Value) {
+    let Value::Object(patch) = patch else {
+        *target = patch;
+        return;
+    };
+    if !target.is_object() {
+        *target = Value::Object(serde_json::Map::new());
+    }
+    let Value::Object(target) = target else {
+        return;
+    };
+    for (key, value) in patch {
+        if value.is_null() {
+            target.remove(&key);
+        } else {
+            merge_patch(target.entry(key).or_insert(Value::Null), value);
+        }
+    }
+}
+
+/// Creates `successor` as the renewal of investment `id` and closes the original,
+/// linking the two both ways in one transaction. An investment can only be renewed once.
+pub async fn renew_inv(id: String, mut successor: Investment) -> Result<Renewal> {
+    let original = get_inv(id.clone()).await?;
+    check_transition(&original, Lifecycle::Renewed)?;
+
+    validate_inv(&successor)?;
+    let now = Utc::now();
+    // the successor's id is picked here so the original can name it in the same transaction
+    let successor_id = Thing::from((INVESTMENT, Id::rand()));
+    clear_server_owned(&mut successor);
+    successor.id = Some(successor_id.clone());
+    successor.renewed_from = Some(id.clone());
+    successor.created_at = Some(now);
+    successor.updated_at = Some(now);
+
+    let mut closing = original.clone();
+    closing.renewed_to = Some(successor_id.to_string());
+    closing.inv_status = Some(InvStatus {
+        id: original.inv_status.clone().and_then(|status| status.id),
+        status: Lifecycle::Renewed.to_string(),
+    });
+    closing.updated_at = Some(now);
+
+    // checked inside the transaction so two renewals at once can't both pass;
+    // renewals made before `renewed_to` existed are only linked from the successor
+    let sql = "BEGIN TRANSACTION; \
+        IF $original_id.renewed_to != NONE \
+            OR (SELECT VALUE id FROM type::table($table) WHERE renewed_from = $id AND deleted_at = NONE LIMIT 1) != [] \
+            { THROW \"already renewed\" }; \
+        CREATE $successor_id CONTENT $successor; \
+        UPDATE $original_id CONTENT $original; \
+        COMMIT TRANSACTION;";
+    let mut response = DB
+        .query(sql)
+        .bind(("table", INVESTMENT))
+        .bind(("id", &id))
+        .bind(("successor_id", successor_id))
+        .bind(("successor", &successor))
+        .bind(("original_id", original.id.clone()))
+        .bind(("original", &closing))
+        .await?;
+    let errors = response.take_errors();
+    if errors
+        .values()
+        .any(|error| error.to_string().contains("already renewed"))
+    {
+        return Err(Error::Conflict(format!(
+            "Investment {id} has already been renewed"
+        )));
+    }
+    if let Some(error) = errors.into_values().next() {
+        return Err(error.into());
+    }
+    cache::invalidate();
+    // BEGIN and COMMIT give no results, so the check is 0, the create 1 and the update 2
+    let renewed: Option<Investment> = response.take(1)?;
+    let closed: Option<Investment> = response.take(2)?;
+    let (Some(renewed), Some(closed)) = (renewed, closed) else {
+        return Err(Error::Generic("Failed to renew investment".into()));
+    };
+    record_audit("update", original).await;
+
+    Ok(Renewal { closed, renewed })
+}
+
+/// Every investment in the renewal chain `id` belongs to, oldest first.
+pub async fn get_renewal_chain(id: String) -> Result<Vec<Investment>> {
+    let inv = get_inv(id).await?;
+
+    let mut earlier = Vec::new();
+    let mut from = inv.renewed_from.clone();
+    while let Some(id) = from {
+        // a deleted link ends the chain rather than failing it
+        let Ok(previous) = get_inv(id).await else {
+            break;
+        };
+        if previous.id == inv.id || earlier.iter().any(|inv: &Investment| inv.id == previous.id) {
+            break;
+        }
+        from = previous.renewed_from.clone();
+        earlier.push(previous);
+    }
+    earlier.reverse();
+
+    let mut chain = earlier;
+    let mut next = inv.id.as_ref().map(ToString::to_string);
+    chain.push(inv);
+    let sql =
+        "SELECT * FROM type::table($table) WHERE renewed_from = $id AND deleted_at = NONE LIMIT 1;";
+    while let Some(id) = next {
+        let mut response = DB
+            .query(sql)
+            .bind(("table", INVESTMENT))
+            .bind(("id", &id))
+            .await?;
+        let successor: Option<Investment> = response.take(0)?;
+        let Some(successor) = successor else {
+            break;
+        };
+        // stored links can't form a loop through the API, but don't hang if one does
+        if chain.iter().any(|inv| inv.id == successor.id) {
+            break;
+        }
+        next = successor.id.as_ref().map(ToString::to_string);
+        chain.push(successor);
+    }
+
+    Ok(chain)
+}
+
+/// Closes investment `id` without renewing it, e.g. when it was withdrawn early.
+pub async fn close_inv(id: String) -> Result<Investment> {
+    let inv = get_inv(id.clone()).await?;
+    check_transition(&inv, Lifecycle::Closed)?;
+
+    patch_inv(
+        id,
+        serde_json::json!({ "inv_status": { "status": Lifecycle::Closed } }),
+    )
+    .await
+}
+
+/// Conflict unless `inv` may move from where it is now to `next`.
+fn check_transition(inv: &Investment, next: Lifecycle) -> Result<()> {
+    let current = inv.status(Utc::now());
+    if current.can_become(next) {
+        return Ok(());
+    }
+    let id = inv.id.as_ref().map(ToString::to_string).unwrap_or_default();
+
+    Err(Error::Conflict(format!(
+        "Investment {id} is {current} and can't be {next}"
+    )))
+}
+
+pub async fn set_archived(id: String, archived: bool) -> Result<Investment> {
+    patch_inv(id, serde_json::json!({ "archived": archived })).await
+}
+
+/// Deletes, as `delete_inv` does, every investment in `ids` in one query.
+/// Ids that are unknown or already deleted are skipped and not counted.
+pub async fn delete_invs(ids: Vec<String>) -> Result<AffectedRows> {
+    let previous = listed_invs(&ids).await?;
+    if previous.is_empty() {
+        return Ok(AffectedRows { rows_affected: 0 });
+    }
+
+    let things: Vec<&Thing> = previous.iter().filter_map(|inv| inv.id.as_ref()).collect();
+    let sql = "UPDATE $ids SET deleted_at = $now;";
+    DB.query(sql)
+        .bind(("ids", things))
+        .bind(("now", Utc::now()))
+        .await?
+        .check()?;
+    cache::invalidate();
+    let rows_affected = previous.len() as u64;
+    for inv in previous {
+        record_audit("delete", inv).await;
+    }
+
+    Ok(AffectedRows { rows_affected })
+}
+
+/// Archives every investment in `ids` in one query; unknown, deleted and
+/// already archived ones are skipped and not counted.
+pub async fn archive_invs(ids: Vec<String>) -> Result<AffectedRows> {
+    let previous: Vec<Investment> = listed_invs(&ids)
+        .await?
+        .into_iter()
+        .filter(|inv| !inv.archived)
+        .collect();
+    if previous.is_empty() {
+        return Ok(AffectedRows { rows_affected: 0 });
+    }
+
+    let things: Vec<&Thing> = previous.iter().filter_map(|inv| inv.id.as_ref()).collect();
+    let sql = "UPDATE $ids SET archived = true, updated_at = $now;";
+    DB.query(sql)
+        .bind(("ids", things))
+        .bind(("now", Utc::now()))
+        .await?
+        .check()?;
+    cache::invalidate();
+    let rows_affected = previous.len() as u64;
+    for inv in previous {
+        record_audit("update", inv).await;
+    }
+
+    Ok(AffectedRows { rows_affected })
+}
+
+/// The investments in `ids` that exist and aren't deleted. UPDATE on a record
+/// id would create it, so a bulk change only ever touches the ones found here.
+async fn listed_invs(ids: &[String]) -> Result<Vec<Investment>> {
+    let mut things = Vec::with_capacity(ids.len());
+    for id in ids {
+        let (table, key) = id
+            .split_once(':')
+            .ok_or(Error::BadRequest(format!("Invalid investment id: {id}")))?;
+        let thing = Thing::from((table, key));
+        client_key(&thing)?;
+        things.push(thing);
+    }
+    if things.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let sql = "SELECT * FROM $ids WHERE deleted_at = NONE;";
+    let mut response = DB.query(sql).bind(("ids", things)).await?;
+    let invs: Vec<Investment> = response.take(0)?;
+
+    Ok(invs)
+}
+
+/// One `GET /invs` page of investments.
+pub struct InvList {
+    pub investments: Vec<Investment>,
+    /// More rows match after this page.
+    pub truncated: bool,
+    /// The page size applied.
+    pub limit: usize,
+    /// Rows skipped before this page, worked out from `page` when that was sent.
+    pub offset: usize,
+    /// Rows matching the filters across all pages; only counted for an envelope.
+    pub total: Option<u64>,
+    /// Where the next page starts, when paging by cursor and more rows follow.
+    pub next_cursor: Option<String>,
+}
+
+pub async fn get_all_invs(query: &ListQuery) -> Result<InvList> {
     // let tasks: Vec<Task> = DB.select(TASK).await?;
 
     // Ok(tasks)
-    let sql = "SELECT * FROM type::table($table) ORDER BY created_at DESC;";
+    let ids = list_ids(query.ids.as_deref())?;
+    // records created before archiving existed have no `archived` field
+    let mut conditions = vec![if ids.is_some() {
+        "id IN $ids"
+    } else if query.archived {
+        "archived = true"
+    } else {
+        "archived != true"
+    }];
+    if !query.include_deleted {
+        conditions.push("deleted_at = NONE");
+    }
+    let inv_type = query.inv_type.as_deref().map(str::trim);
+    if let Some(inv_type) = inv_type {
+        if inv_type.is_empty() {
+            return Err(Error::BadRequest("inv_type must not be blank".into()));
+        }
+        conditions.push("inv_type = $inv_type");
+    }
+    let before = list_date("maturing_before", query.maturing_before.as_deref())?;
+    if before.is_some() {
+        conditions.push("end_date < $before");
+    }
+    let after = list_date("maturing_after", query.maturing_after.as_deref())?;
+    if after.is_some() {
+        conditions.push("end_date >= $after");
+    }
+    let start_after = list_date("start_after", query.start_after.as_deref())?;
+    if start_after.is_some() {
+        conditions.push("start_date >= $start_after");
+    }
+    if query.min_amount.is_some() {
+        conditions.push("inv_amount >= $min_amount");
+    }
+    if query.max_amount.is_some() {
+        conditions.push("inv_amount <= $max_amount");
+    }
+    if let (Some(min_amount), Some(max_amount)) = (query.min_amount, query.max_amount) {
+        if min_amount > max_amount {
+            return Err(Error::BadRequest(
+                "min_amount must not be more than max_amount".into(),
+            ));
+        }
+    }
+    if let Some(min_rate) = query.min_rate {
+        if !(0.0..=100.0).contains(&min_rate) {
+            return Err(Error::BadRequest(
+                "min_rate must be between 0 and 100".into(),
+            ));
+        }
+        conditions.push("return_rate >= $min_rate");
+    }
+    let limit = query.limit.unwrap_or(CONFIG.default_page_size);
+    if limit == 0 {
+        return Err(Error::BadRequest("limit must be at least 1".into()));
+    }
+    let limit = limit.min(CONFIG.max_list_rows);
+    let offset = match query.page {
+        Some(0) => return Err(Error::BadRequest("page must be at least 1".into())),
+        Some(page) => (page - 1).saturating_mul(limit),
+        None => query.offset,
+    };
+    let status = query
+        .status
+        .as_deref()
+        .map(str::parse::<Lifecycle>)
+        .transpose()
+        .map_err(|message| Error::Invalid {
+            field: "status",
+            message,
+        })?;
+    if let Some(status) = status {
+        conditions.push(status_condition(status));
+    }
+    let by_cursor = query.after.is_some();
+    if by_cursor && (query.sort.is_some() || query.page.is_some() || query.offset > 0) {
+        return Err(Error::BadRequest(
+            "after can't be combined with sort, page or offset".into(),
+        ));
+    }
+    let cursor = query
+        .after
+        .as_deref()
+        .map(parse_cursor)
+        .transpose()?
+        .flatten();
+    // the total counts every matching row, not just those past the cursor
+    let count_filter = conditions.join(" AND ");
+    if cursor.is_some() {
+        conditions
+            .push("(created_at > $cursor_at OR (created_at = $cursor_at AND id > $cursor_id))");
+    }
+    // rows created while paging land after the cursor, so nothing is skipped or repeated
+    let order = if by_cursor {
+        "created_at ASC".to_string()
+    } else {
+        list_order(query.sort.as_deref())?
+    };
+    let filter = conditions.join(" AND ");
+    // every ORDER BY here ends on the id so ties come back in the same order each time.
+    // One row past the page tells us whether there is more.
+    let mut sql = format!(
+        "SELECT *, {NEVER_REORDERED} FROM type::table($table) WHERE {filter} ORDER BY {order}, id ASC LIMIT {} START {};",
+        limit + 1,
+        offset
+    );
+    if query.envelope {
+        sql.push_str(&format!(
+            "SELECT count() AS total FROM type::table($table) WHERE {count_filter} GROUP ALL;"
+        ));
+    }
+
+    let mut response = DB
+        .query(sql)
+        .bind(("table", INVESTMENT))
+        .bind(("inv_type", inv_type))
+        .bind(("before", before))
+        .bind(("after", after))
+        .bind(("min_rate", query.min_rate))
+        .bind(("start_after", start_after))
+        .bind(("min_amount", query.min_amount))
+        .bind(("max_amount", query.max_amount))
+        .bind(("now", Utc::now()))
+        .bind(("ids", ids))
+        .bind(("cursor_at", cursor.as_ref().map(|(at, _)| *at)))
+        .bind(("cursor_id", cursor.map(|(_, id)| id)))
+        .await?;
+
+    let mut tasks: Vec<Investment> = response.take(0)?;
+    let truncated = tasks.len() > limit;
+    tasks.truncate(limit);
+    let total = if query.envelope {
+        // no matching rows means no group, hence no count at all
+        Some(response.take::<Option<u64>>((1, "total"))?.unwrap_or(0))
+    } else {
+        None
+    };
+
+    let next_cursor = if by_cursor && truncated {
+        tasks.last().and_then(cursor_of)
+    } else {
+        None
+    };
+
+    Ok(InvList {
+        investments: tasks,
+        truncated,
+        limit,
+        offset,
+        total,
+        next_cursor,
+    })
+}
+
+/// Fills an empty table with `sample_invs()`; does nothing once any
+/// investment exists, archived ones included.
+pub async fn seed_invs() -> Result<AffectedRows> {
+    let sql = "SELECT id FROM type::table($table) LIMIT 1;";
+    let mut response = DB.query(sql).bind(("table", INVESTMENT)).await?;
+    let existing: Option<Record> = response.take(0)?;
+    if existing.is_some() {
+        return Ok(AffectedRows { rows_affected: 0 });
+    }
+
+    let mut rows_affected = 0;
+    for mut inv in sample_invs() {
+        add_inv(&mut inv).await?;
+        rows_affected += 1;
+    }
+
+    Ok(AffectedRows { rows_affected })
+}
+
+/// Adds every investment in a backup, as checked by `backup::read`, in one
+/// transaction, so a failure leaves nothing half imported. Ids and timestamps
+/// are kept. Investments whose id already exists are left alone and not
+/// counted, so importing the same backup twice adds nothing the second time.
+pub async fn import_invs(investments: Vec<Investment>) -> Result<AffectedRows> {
+    let now = Utc::now();
+    let mut records = Vec::with_capacity(investments.len());
+    for mut inv in investments {
+        let id = match inv.id.take() {
+            Some(id) => Thing::from((INVESTMENT, client_key(&id)?.as_str())),
+            None => Thing::from((INVESTMENT, Id::rand())),
+        };
+        // backups written before the timestamps existed get them now
+        inv.created_at.get_or_insert(now);
+        inv.updated_at.get_or_insert(now);
+        records.push((id, inv));
+    }
+
+    let ids: Vec<&Thing> = records.iter().map(|(id, _)| id).collect();
+    let sql = "SELECT VALUE id FROM type::table($table) WHERE id IN $ids;";
+    let mut response = DB
+        .query(sql)
+        .bind(("table", INVESTMENT))
+        .bind(("ids", ids))
+        .await?;
+    let existing: Vec<Thing> = response.take(0)?;
+    records.retain(|(id, _)| !existing.contains(id));
+    if records.is_empty() {
+        return Ok(AffectedRows { rows_affected: 0 });
+    }
+
+    let mut sql = String::from("BEGIN TRANSACTION;");
+    for statement in 0..records.len() {
+        sql.push_str(&format!("CREATE $id{statement} CONTENT $inv{statement};"));
+    }
+    sql.push_str("COMMIT TRANSACTION;");
+    let mut query = DB.query(sql);
+    for (statement, (id, inv)) in records.iter().enumerate() {
+        query = query
+            .bind((format!("id{statement}"), id))
+            .bind((format!("inv{statement}"), inv));
+    }
+    query.await?.check()?;
+    cache::invalidate();
+
+    Ok(AffectedRows {
+        rows_affected: records.len() as u64,
+    })
+}
+
+/// Creates every valid investment in `investments` in one transaction; the
+/// invalid ones are reported back instead. Either all the valid ones are
+/// stored or, should the transaction fail, none are.
+pub async fn add_invs(investments: Vec<Investment>) -> Result<Vec<BatchItem>> {
+    let now = Utc::now();
+    let mut results = Vec::with_capacity(investments.len());
+    let mut valid = Vec::new();
+    for (index, mut inv) in investments.into_iter().enumerate() {
+        clear_server_owned(&mut inv);
+        let error = if inv.id.is_some() {
+            Some("id: is assigned by the server in a batch".to_string())
+        } else {
+            validate_inv(&inv).err().map(|err| err.to_string())
+        };
+        if error.is_none() {
+            inv.created_at = Some(now);
+            inv.updated_at = Some(now);
+            valid.push((index, inv));
+        }
+        // the id is filled in once the transaction has gone through
+        results.push(BatchItem { id: None, error });
+    }
+    if valid.is_empty() {
+        return Ok(results);
+    }
+
+    let mut sql = String::from("BEGIN TRANSACTION;");
+    for statement in 0..valid.len() {
+        sql.push_str(&format!(
+            "CREATE type::table($table) CONTENT $inv{statement};"
+        ));
+    }
+    sql.push_str("COMMIT TRANSACTION;");
+    let mut query = DB.query(sql).bind(("table", INVESTMENT));
+    for (statement, (_, inv)) in valid.iter().enumerate() {
+        query = query.bind((format!("inv{statement}"), inv));
+    }
+    let mut response = query.await?;
+    cache::invalidate();
+
+    // BEGIN and COMMIT give no results, so the creates are numbered from 0
+    for (statement, (index, _)) in valid.iter().enumerate() {
+        let created: Option<Investment> = response.take(statement)?;
+        results[*index].id = created.and_then(|inv| inv.id);
+    }
+
+    Ok(results)
+}
+
+/// A small portfolio for demos: both investment types and both return types.
+fn sample_invs() -> Vec<Investment> {
+    let date = |year, month, day| Utc.with_ymd_and_hms(year, month, day, 0, 0, 0).single();
+
+    vec![
+        Investment {
+            inv_name: "SBI Tax Saver".into(),
+            inv_type: "FD".into(),
+            return_rate: 650,
+            return_type: "Culmulative".into(),
+            inv_amount: 150_000,
+            return_amount: 206_538,
+            name: "Demo".into(),
+            start_date: date(2023, 4, 1),
+            end_date: date(2028, 4, 1),
+            ..Default::default()
+        },
+        Investment {
+            inv_name: "HDFC Monthly".into(),
+            inv_type: "RD".into(),
+            return_rate: 700,
+            return_type: "Ordinary".into(),
+            inv_amount: 60_000,
+            return_amount: 64_200,
+            name: "Demo".into(),
+            start_date: date(2024, 1, 15),
+            end_date: date(2025, 1, 15),
+            ..Default::default()
+        },
+        Investment {
+            inv_name: "Post Office MIS".into(),
+            inv_type: "FD".into(),
+            return_rate: 740,
+            return_type: "Ordinary".into(),
+            inv_amount: 450_000,
+            return_amount: 616_500,
+            name: "Demo".into(),
+            start_date: date(2022, 7, 1),
+            end_date: date(2027, 7, 1),
+            ..Default::default()
+        },
+    ]
+}
+
+/// Every investment, archived ones included, for reports that cover past years.
+/// Deleted ones are left out.
+pub async fn get_every_inv() -> Result<Vec<Investment>> {
+    let sql = "SELECT * FROM type::table($table) WHERE deleted_at = NONE ORDER BY id ASC;";
+    let mut response = DB.query(sql).bind(("table", INVESTMENT)).await?;
+    let invs: Vec<Investment> = response.take(0)?;
+
+    Ok(invs)
+}
+
+/// The investments the portfolio still holds: neither archived nor deleted,
+/// and not closed or renewed into another one.
+pub async fn get_held_invs() -> Result<Vec<Investment>> {
+    let now = Utc::now();
+    let held = get_every_inv()
+        .await?
+        .into_iter()
+        .filter(|inv| !inv.archived && held_at(inv, now))
+        .collect();
+
+    Ok(held)
+}
+
+/// Whether `inv` still counts towards the portfolio as of `now`.
+fn held_at(inv: &Investment, now: DateTime<Utc>) -> bool {
+    !matches!(inv.status(now), Lifecycle::Closed | Lifecycle::Renewed)
+}
+
+/// Compares every stored `return_amount` with the maturity value its rate and
+/// dates give, and unless `dry_run` writes the computed value back. An
+/// investment that can't be saved is reported as skipped; the rest go on.
+pub async fn recompute_return_amounts(dry_run: bool) -> Result<RecomputeReport> {
+    let options = CONFIG.calc_options();
+    let mut report = RecomputeReport {
+        dry_run,
+        ..RecomputeReport::default()
+    };
+
+    for mut inv in get_every_inv().await? {
+        if inv.start_date.is_none() || inv.end_date.is_none() {
+            continue;
+        }
+        report.checked += 1;
+        let computed = inv.maturity_value_with(&options);
+        if computed == inv.return_amount {
+            continue;
+        }
+
+        let stored = inv.return_amount;
+        let investment = inv.id.as_ref().map(ToString::to_string).unwrap_or_default();
+        let previous = inv.clone();
+        inv.return_amount = computed;
+        inv.updated_at = Some(Utc::now());
+        // checked up front too, so a dry run skips what the real run would
+        let errors = validation::validate_update(&inv, &previous);
+        let saved = if !errors.is_empty() {
+            Err(Error::Validation(errors))
+        } else if dry_run {
+            Ok(())
+        } else {
+            update_inv(&mut inv).await.map(drop)
+        };
+        if let Err(err) = saved {
+            report.skipped.push(RecomputeSkipped {
+                investment,
+                reason: err.to_string(),
+            });
+            continue;
+        }
+        report.total_difference += computed - stored;
+        report.changed.push(RecomputedAmount {
+            investment,
+            stored,
+            computed,
+        });
+    }
+
+    Ok(report)
+}
+
+/// Gives the investments in `ids` sort orders `offset + 1`, `offset + 2`...
+/// in the order listed, so reordering one page leaves the pages before it alone.
+pub async fn reorder_invs(ids: Vec<String>, offset: usize) -> Result<AffectedRows> {
+    let mut keys = Vec::with_capacity(ids.len());
+    for id in &ids {
+        match id.split_once(':') {
+            Some((INVESTMENT, key)) if !keys.contains(&key) => keys.push(key),
+            Some((INVESTMENT, _)) => {
+                return Err(Error::BadRequest(format!(
+                    "Investment {id} is listed twice"
+                )))
+            }
+            _ => return Err(Error::BadRequest(format!("Invalid investment id: {id}"))),
+        }
+    }
+    if keys.is_empty() {
+        return Ok(AffectedRows { rows_affected: 0 });
+    }
+
+    // UPDATE on a record id would create it, so unknown ids are looked up first
+    let things: Vec<Thing> = keys
+        .iter()
+        .map(|key| Thing::from((INVESTMENT, *key)))
+        .collect();
+    let sql = "SELECT VALUE id FROM $ids WHERE deleted_at = NONE;";
+    let mut response = DB.query(sql).bind(("ids", &things)).await?;
+    let existing: Vec<Thing> = response.take(0)?;
+    let positioned: Vec<(usize, Thing)> = things
+        .into_iter()
+        .enumerate()
+        .filter(|(_, thing)| existing.contains(thing))
+        .collect();
+    if positioned.is_empty() {
+        return Ok(AffectedRows { rows_affected: 0 });
+    }
+
+    let sql: String = positioned
+        .iter()
+        .enumerate()
+        .map(|(i, (position, _))| {
+            format!(
+                "UPDATE $id{i} SET sort_order = {}, updated_at = $now RETURN id;",
+                offset + position + 1
+            )
+        })
+        .collect();
+    let mut query = DB.query(sql).bind(("now", Utc::now()));
+    for (i, (_, thing)) in positioned.iter().enumerate() {
+        query = query.bind((format!("id{i}"), thing));
+    }
+    let mut response = query.await?;
+
+    let mut rows_affected = 0;
+    for i in 0..positioned.len() {
+        let updated: Vec<Record> = response.take(i)?;
+        rows_affected += updated.len() as u64;
+    }
+    cache::invalidate();
+
+    Ok(AffectedRows { rows_affected })
+}
+
+/// Every investor name in use, sorted, for autocompleting the name field.
+pub async fn get_investor_names() -> Result<Vec<String>> {
+    let sql = "SELECT array::distinct(name) AS names FROM type::table($table) WHERE deleted_at = NONE GROUP ALL;";
 
     let mut response = DB.query(sql).bind(("table", INVESTMENT)).await?;
 
-    let tasks: Vec<Investment> = response.take(0)?;
+    let mut names: Vec<String> = response
+        .take::<Option<Vec<String>>>((0, "names"))?
+        .unwrap_or_default();
+    names.retain(|name| !name.is_empty());
+    names.sort();
 
-    Ok(tasks)
+    Ok(names)
+}
+
+/// Investments that look like the same one entered again: same name (ignoring
+/// case), same amount, starting on the same day.
+pub async fn find_duplicates(name: &str, amount: i64, start: &str) -> Result<Vec<Investment>> {
+    let Some(day) = list_date("start", Some(start))? else {
+        return Ok(Vec::new());
+    };
+    let sql = "SELECT * FROM type::table($table) WHERE deleted_at = NONE AND string::lowercase(name) = string::lowercase($name) AND inv_amount = $amount AND start_date >= $day AND start_date < $next_day ORDER BY created_at DESC, id ASC;";
+
+    let mut response = DB
+        .query(sql)
+        .bind(("table", INVESTMENT))
+        .bind(("name", name.trim().to_string()))
+        .bind(("amount", amount))
+        .bind(("day", day))
+        .bind(("next_day", day + chrono::Duration::days(1)))
+        .await?;
+
+    let duplicates: Vec<Investment> = response.take(0)?;
+
+    Ok(duplicates)
+}
+
+/// Active investments whose `inv_name` or investor `name` contains `q`, ignoring
+/// case, best match first: a whole-field match beats a prefix, which beats a
+/// match further in. At most `CONFIG.max_list_rows`.
+pub async fn search_invs(q: &str) -> Result<Vec<Investment>> {
+    let q = q.trim().to_lowercase();
+    if q.is_empty() {
+        return Err(Error::BadRequest("q must not be blank".into()));
+    }
+    let sql = "SELECT * FROM type::table($table) WHERE archived != true AND deleted_at = NONE AND (string::lowercase(inv_name) CONTAINS $q OR string::lowercase(name) CONTAINS $q) ORDER BY created_at DESC, id ASC;";
+
+    let mut response = DB
+        .query(sql)
+        .bind(("table", INVESTMENT))
+        .bind(("q", q.clone()))
+        .await?;
+
+    let matches: Vec<Investment> = response.take(0)?;
+    let mut ranked: Vec<(u32, Investment)> = matches
+        .into_iter()
+        .map(|inv| (search_rank(&inv, &q), inv))
+        .collect();
+    // stable, so equal ranks stay newest first
+    ranked.sort_by(|a, b| b.0.cmp(&a.0));
+
+    Ok(ranked
+        .into_iter()
+        .take(CONFIG.max_list_rows)
+        .map(|(_, inv)| inv)
+        .collect())
+}
+
+/// How well `inv` matches the lowercase query `q`; higher is better.
+fn search_rank(inv: &Investment, q: &str) -> u32 {
+    [&inv.inv_name, &inv.name]
+        .into_iter()
+        .map(|field| {
+            let field = field.to_lowercase();
+            if field == q {
+                3
+            } else if field.starts_with(q) {
+                2
+            } else if field.contains(q) {
+                1
+            } else {
+                0
+            }
+        })
+        .sum()
+}
+
+/// Sums and counts over the investments still held, see `get_held_invs`.
+pub async fn get_stats() -> Result<Stats> {
+    cache::cached("stats", async {
+        let held = get_held_invs().await?;
+
+        let total_invested: i64 = held.iter().map(|inv| inv.inv_amount).sum();
+        let total_return = held.iter().map(|inv| inv.return_amount).sum();
+        let weighted_average_rate = if total_invested > 0 {
+            let weighted: f64 = held
+                .iter()
+                .map(|inv| inv.inv_amount as f64 * inv.return_rate as f64)
+                .sum();
+            // basis points to a percentage, kept to two decimals
+            (weighted / total_invested as f64).round() / 100.0
+        } else {
+            0.0
+        };
+        let mut count_by_type = BTreeMap::new();
+        for inv in &held {
+            *count_by_type.entry(inv.inv_type.clone()).or_default() += 1;
+        }
+
+        Ok(Stats {
+            total_invested,
+            total_return,
+            count_by_type,
+            weighted_average_rate,
+        })
+    })
+    .await
+}
+
+/// Fields `GET /summary` can group by.
+const SUMMARY_GROUPS: [&str; 3] = ["name", "inv_name", "inv_type"];
+
+/// Active investments totalled per distinct value of `group_by`, largest amount invested first.
+pub async fn get_summary(group_by: &str) -> Result<Vec<SummaryGroup>> {
+    let field = SUMMARY_GROUPS
+        .iter()
+        .find(|field| **field == group_by)
+        .ok_or(Error::Invalid {
+            field: "group_by",
+            message: format!("must be one of {}", SUMMARY_GROUPS.join(", ")),
+        })?;
+    // `field` is one of ours, never the client's text
+    let sql = format!(
+        "SELECT {field}, count() AS count, math::sum(inv_amount) AS invested, math::sum(return_amount) AS returns FROM type::table($table) WHERE archived != true AND deleted_at = NONE GROUP BY {field};"
+    );
+
+    let mut response = DB.query(sql).bind(("table", INVESTMENT)).await?;
+
+    let keys: Vec<String> = response.take((0, *field))?;
+    let counts: Vec<u64> = response.take((0, "count"))?;
+    let invested: Vec<i64> = response.take((0, "invested"))?;
+    let returns: Vec<i64> = response.take((0, "returns"))?;
+    let mut groups: Vec<SummaryGroup> = keys
+        .into_iter()
+        .zip(counts)
+        .zip(invested.into_iter().zip(returns))
+        .map(|((key, count), (invested, returns))| SummaryGroup {
+            key,
+            count,
+            invested,
+            returns,
+        })
+        .collect();
+    groups.sort_by(|a, b| b.invested.cmp(&a.invested).then_with(|| a.key.cmp(&b.key)));
+
+    Ok(groups)
+}
+
+pub async fn get_next_maturing_inv() -> Result<Option<Investment>> {
+    cache::cached("next-maturing", async {
+        let sql =
+            "SELECT * FROM type::table($table) WHERE end_date > $now AND archived != true AND deleted_at = NONE ORDER BY end_date ASC, id ASC LIMIT 1;";
+
+        let mut response = DB
+            .query(sql)
+            .bind(("table", INVESTMENT))
+            .bind(("now", Utc::now()))
+            .await?;
+
+        let next: Option<Investment> = response.take(0)?;
+
+        Ok(next)
+    })
+    .await
+}
+
+/// Earlier states of investment `id`, newest first. Kept after the investment is deleted.
+pub async fn get_inv_history(id: String) -> Result<Vec<AuditEntry>> {
+    match id.split_once(':') {
+        Some((INVESTMENT, _)) => {}
+        _ => return Err(Error::BadRequest(format!("Invalid investment id: {id}"))),
+    }
+    let sql = "SELECT * FROM type::table($table) WHERE investment = $id ORDER BY recorded_at DESC, id ASC;";
+    let mut response = DB
+        .query(sql)
+        .bind(("table", AUDIT))
+        .bind(("id", id))
+        .await?;
+
+    let history: Vec<AuditEntry> = response.take(0)?;
+
+    Ok(history)
+}
+
+/// Registers a reminder `days_before` investment `id` matures.
+pub async fn add_reminder(id: String, days_before: i64, email: String) -> Result<Reminder> {
+    check_reminder(days_before, &email)?;
+    let inv = get_inv(id.clone()).await?;
+    let Some(end_date) = inv.end_date else {
+        return Err(Error::Invalid {
+            field: "end_date",
+            message: "the investment has no maturity date to be reminded of".to_string(),
+        });
+    };
+    let reminder = Reminder {
+        id: None,
+        investment: id,
+        days_before,
+        email: email.trim().to_string(),
+        due_at: end_date - chrono::Duration::days(days_before),
+        created_at: Utc::now(),
+        sent_at: None,
+    };
+
+    let created: Vec<Reminder> = DB.create(REMINDER).content(reminder).await?;
+
+    created
+        .into_iter()
+        .next()
+        .ok_or(Error::Generic("Failed to create record".into()))
+}
+
+/// Reminders whose day has come and that haven't been sent, oldest first, for
+/// a notifier to poll. Those of a deleted investment are left out.
+pub async fn get_due_reminders() -> Result<Vec<Reminder>> {
+    // `investment` holds the id as `add_reminder` got it, `table:key` with the key unescaped
+    let sql = "
+        SELECT * FROM type::table($table)
+        WHERE due_at <= $now AND sent_at = NONE
+            AND investment INSIDE (
+                SELECT VALUE string::concat($investments, ':', meta::id(id))
+                FROM type::table($investments) WHERE deleted_at = NONE
+            )
+        ORDER BY due_at ASC, id ASC;
+    ";
+    let mut response = DB
+        .query(sql)
+        .bind(("table", REMINDER))
+        .bind(("investments", INVESTMENT))
+        .bind(("now", Utc::now()))
+        .await?;
+
+    let due: Vec<Reminder> = response.take(0)?;
+
+    Ok(due)
+}
+
+/// Records that the notifier delivered reminder `id`, so it stops being due.
+/// Marking it again keeps the first delivery time.
+pub async fn mark_reminder_sent(id: String) -> Result<Reminder> {
+    let (table, key) = id
+        .split_once(':')
+        .filter(|(table, _)| *table == REMINDER)
+        .ok_or(Error::BadRequest(format!("Invalid reminder id: {id}")))?;
+    let thing = Thing::from((table, key));
+    // UPDATE would create a missing record, so only ever mark one that exists
+    let existing: Option<Reminder> = DB.select(thing.clone()).await?;
+    let existing = existing.ok_or(Error::NotFound(format!("Reminder {id} not found")))?;
+    if existing.sent_at.is_some() {
+        return Ok(existing);
+    }
+
+    let sql = "UPDATE $id SET sent_at = $now RETURN AFTER;";
+    let mut response = DB
+        .query(sql)
+        .bind(("id", thing))
+        .bind(("now", Utc::now()))
+        .await?;
+    let sent: Option<Reminder> = response.take(0)?;
+
+    sent.ok_or(Error::Generic("Failed to update record".into()))
+}
+
+/// Appends the state an investment had before `action` to its history.
+/// Best effort: a failure is logged, never returned, so the write itself stands.
+async fn record_audit(action: &str, previous: Investment) {
+    let Some(id) = previous.id.as_ref().map(ToString::to_string) else {
+        return;
+    };
+    let entry = AuditEntry {
+        investment: id,
+        action: action.to_string(),
+        previous,
+        recorded_at: Utc::now(),
+    };
+    let created: surrealdb::Result<Vec<Record>> = DB.create(AUDIT).content(entry).await;
+    if let Err(error) = created {
+        log::warn!(
+            "[{}] audit entry not recorded: {error}",
+            crate::request_id::current()
+        );
+    }
+}
+
+/// Checks a client-supplied id and returns its key part.
+fn client_key(id: &Thing) -> Result<String> {
+    let key = match &id.id {
+        Id::String(key) => key.clone(),
+        Id::Number(key) => key.to_string(),
+        _ => return Err(Error::BadRequest(format!("Invalid investment id: {id}"))),
+    };
+    let valid_key = !key.is_empty()
+        && key.len() <= 64
+        && key
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-');
+    if id.tb != INVESTMENT || !valid_key {
+        return Err(Error::BadRequest(format!("Invalid investment id: {id}")));
+    }
+
+    Ok(key)
+}
+
+/// The `GET /invs` condition for investments in `status`, the same way
+/// `Investment::status` tells; it compares end dates with `$now`.
+fn status_condition(status: Lifecycle) -> &'static str {
+    match status {
+        Lifecycle::Closed => "inv_status.status = 'closed'",
+        Lifecycle::Renewed => "(inv_status.status = 'renewed' AND renewed_to != NONE)",
+        Lifecycle::Matured => {
+            "(inv_status.status != 'closed' AND (inv_status.status != 'renewed' OR renewed_to = NONE) \
+            AND end_date <= $now)"
+        }
+        Lifecycle::Active => {
+            "(inv_status.status != 'closed' AND (inv_status.status != 'renewed' OR renewed_to = NONE) \
+            AND (end_date = NONE OR end_date > $now))"
+        }
+    }
+}
+
+/// The cursor naming `inv`: its creation time in nanoseconds and its id, e.g.
+/// `1718000000000000000_investment:abc`.
+fn cursor_of(inv: &Investment) -> Option<String> {
+    let created_at = inv.created_at?.timestamp_nanos_opt()?;
+
+    Some(format!("{created_at}_{}", inv.id.as_ref()?))
+}
+
+/// Reads a cursor from `cursor_of`; an empty one starts from the beginning.
+fn parse_cursor(cursor: &str) -> Result<Option<(DateTime<Utc>, Thing)>> {
+    if cursor.is_empty() {
+        return Ok(None);
+    }
+    let invalid = || Error::BadRequest(format!("Invalid cursor: {cursor}"));
+    let (nanos, id) = cursor.split_once('_').ok_or_else(invalid)?;
+    let nanos: i64 = nanos.parse().map_err(|_| invalid())?;
+    let (table, key) = id.split_once(':').ok_or_else(invalid)?;
+    let id = Thing::from((table, key));
+    client_key(&id).map_err(|_| invalid())?;
+
+    Ok(Some((Utc.timestamp_nanos(nanos), id)))
+}
+
+/// Parses the `ids` list filter; a bare key is taken to be an investment's.
+fn list_ids(ids: Option<&str>) -> Result<Option<Vec<Thing>>> {
+    let Some(ids) = ids else {
+        return Ok(None);
+    };
+    let ids = ids
+        .split(',')
+        .map(str::trim)
+        .filter(|id| !id.is_empty())
+        .map(|id| {
+            let id = match id.split_once(':') {
+                Some((table, key)) => Thing::from((table, key)),
+                None => Thing::from((INVESTMENT, id)),
+            };
+            client_key(&id).map(|_| id)
+        })
+        .collect::<Result<Vec<_>>>()?;
+    if ids.is_empty() {
+        return Err(Error::BadRequest("ids must not be blank".into()));
+    }
+
+    Ok(Some(ids))
+}
+
+/// Parses an optional `YYYY-MM-DD` list filter as midnight UTC.
+fn list_date(field: &str, value: Option<&str>) -> Result<Option<DateTime<Utc>>> {
+    let Some(value) = value else {
+        return Ok(None);
+    };
+    let date = NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .map_err(|_| Error::BadRequest(format!("{field} is not a valid date: {value}")))?;
+
+    Ok(date.and_hms_opt(0, 0, 0).map(|midnight| midnight.and_utc()))
+}
+
+/// Fields `GET /invs` can be sorted by.
+const SORTABLE_FIELDS: [&str; 12] = [
+    "inv_name",
+    "inv_type",
+    "return_rate",
+    "return_type",
+    "inv_amount",
+    "return_amount",
+    "name",
+    "start_date",
+    "end_date",
+    "created_at",
+    "updated_at",
+    "sort_order",
+];
+
+/// Projected by `GET /invs` for the default order: true for investments still
+/// at the `sort_order` they were created with. It isn't an `Investment` field,
+/// so it is dropped when the rows are read.
+const NEVER_REORDERED: &str = "(sort_order = 0 OR sort_order = NONE) AS never_reordered";
+
+/// The ORDER BY clause for a `sort` parameter. Only `SORTABLE_FIELDS` get
+/// into the query, so nothing the client sends is spliced in as is.
+fn list_order(sort: Option<&str>) -> Result<String> {
+    let Some(sort) = sort else {
+        // manually ordered investments first, anything never reordered newest first
+        return Ok("never_reordered ASC, sort_order ASC, created_at DESC".into());
+    };
+
+    let mut order = Vec::new();
+    for key in sort.split(',').map(str::trim) {
+        let (field, direction) = match key.strip_prefix('-') {
+            Some(field) => (field, "DESC"),
+            None => (key, "ASC"),
+        };
+        let field = SORTABLE_FIELDS
+            .iter()
+            .find(|sortable| **sortable == field)
+            .ok_or(Error::Invalid {
+                field: "sort",
+                message: format!("cannot sort by \"{field}\""),
+            })?;
+        order.push(format!("{field} {direction}"));
+    }
+
+    Ok(order.join(", "))
+}
+
+/// Ten years; far enough ahead for any term, and keeps the date math in range.
+const MAX_REMINDER_DAYS: i64 = 3650;
+
+fn check_reminder(days_before: i64, email: &str) -> Result<()> {
+    if !(1..=MAX_REMINDER_DAYS).contains(&days_before) {
+        return Err(Error::Invalid {
+            field: "days_before",
+            message: format!("must be positive and at most {MAX_REMINDER_DAYS}"),
+        });
+    }
+    // a sanity check for typos, not a full RFC 5322 parse
+    let well_formed = match email.trim().split_once('@') {
+        Some((local, domain)) => {
+            !local.is_empty()
+                && !domain.contains('@')
+                && domain.contains('.')
+                && !domain.starts_with('.')
+                && !domain.ends_with('.')
+                && !email.trim().contains(char::is_whitespace)
+        }
+        None => false,
+    };
+    if !well_formed {
+        return Err(Error::Invalid {
+            field: "email",
+            message: "is not a valid email address".to_string(),
+        });
+    }
+
+    Ok(())
+}
+
+/// Clears what only the server sets on an investment: its recorded status,
+/// renewal links, archiving and deletion. A new investment starts active,
+/// however it was sent.
+fn clear_server_owned(inv: &mut Investment) {
+    inv.inv_status = None;
+    inv.renewed_from = None;
+    inv.renewed_to = None;
+    inv.archived = false;
+    inv.deleted_at = None;
+}
+
+/// Every rule a whole investment must satisfy before it is stored.
+fn validate_inv(inv: &Investment) -> Result<()> {
+    let errors = validation::validate(inv);
+    if !errors.is_empty() {
+        return Err(Error::Validation(errors));
+    }
+
+    Ok(())
 }
 
 /*
@@ -72,3 +1538,150 @@ pub async fn get_all_invs() -> Result<Vec<Investment>> {
  * Is there a way to simplify converting an Option into a Result without a macro?
  * https://stackoverflow.com/questions/37890405/is-there-a-way-to-simplify-converting-an-option-into-a-result-without-a-macro
  */
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn with_status(status: &str) -> Investment {
+        Investment {
+            inv_status: Some(InvStatus {
+                id: None,
+                status: status.into(),
+            }),
+            renewed_to: Some("investment:next".into()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn closed_and_renewed_investments_are_not_held() {
+        let now = Utc::now();
+
+        assert!(!held_at(&with_status("closed"), now));
+        assert!(!held_at(&with_status("renewed"), now));
+        assert!(held_at(&with_status("active"), now));
+        assert!(held_at(&Investment::default(), now));
+    }
+
+    #[test]
+    fn a_cursor_reads_back_as_the_row_it_names() {
+        let created_at = Utc.with_ymd_and_hms(2024, 6, 10, 6, 13, 20).unwrap();
+        let id = Thing::from((INVESTMENT, "abc123"));
+        let inv = Investment {
+            id: Some(id.clone()),
+            created_at: Some(created_at),
+            ..Default::default()
+        };
+
+        let cursor = cursor_of(&inv).unwrap();
+
+        assert_eq!(cursor, "1718000000000000000_investment:abc123");
+        assert_eq!(parse_cursor(&cursor).unwrap(), Some((created_at, id)));
+        assert_eq!(parse_cursor("").unwrap(), None);
+    }
+
+    #[test]
+    fn a_malformed_cursor_is_rejected() {
+        for cursor in [
+            "abc",
+            "soon_investment:abc123",
+            "1718000000000000000_abc123",
+            "1718000000000000000_user:abc123",
+            "1718000000000000000_investment:",
+        ] {
+            assert!(
+                matches!(parse_cursor(cursor), Err(Error::BadRequest(_))),
+                "{cursor}"
+            );
+        }
+    }
+
+    #[test]
+    fn the_default_order_puts_manually_ordered_investments_first() {
+        assert_eq!(
+            list_order(None).unwrap(),
+            "never_reordered ASC, sort_order ASC, created_at DESC"
+        );
+    }
+
+    #[test]
+    fn each_sort_key_becomes_a_column_and_direction() {
+        assert_eq!(
+            list_order(Some("-end_date, inv_name")).unwrap(),
+            "end_date DESC, inv_name ASC"
+        );
+    }
+
+    #[test]
+    fn an_unknown_sort_key_is_refused() {
+        for sort in ["deleted_at", "name; DELETE investment", ""] {
+            assert!(
+                matches!(
+                    list_order(Some(sort)),
+                    Err(Error::Invalid { field: "sort", .. })
+                ),
+                "{sort}"
+            );
+        }
+    }
+
+    fn named(inv_name: &str, name: &str) -> Investment {
+        Investment {
+            inv_name: inv_name.into(),
+            name: name.into(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn an_exact_name_ranks_above_a_prefix_and_a_prefix_above_a_match_inside() {
+        let exact = search_rank(&named("SBI", "Asha"), "sbi");
+        let prefix = search_rank(&named("SBI Tax Saver", "Asha"), "sbi");
+        let inside = search_rank(&named("Flexi SBI", "Asha"), "sbi");
+
+        assert_eq!((exact, prefix, inside), (3, 2, 1));
+        assert_eq!(search_rank(&named("HDFC", "Asha"), "sbi"), 0);
+    }
+
+    #[test]
+    fn both_names_add_to_the_rank() {
+        assert_eq!(search_rank(&named("Asha FD", "Asha"), "asha"), 5);
+    }
+
+    #[test]
+    fn a_reminder_needs_a_sensible_lead_time() {
+        assert!(check_reminder(7, "asha@example.com").is_ok());
+        assert!(check_reminder(MAX_REMINDER_DAYS, "asha@example.com").is_ok());
+        for days_before in [0, -1, MAX_REMINDER_DAYS + 1] {
+            assert!(matches!(
+                check_reminder(days_before, "asha@example.com"),
+                Err(Error::Invalid {
+                    field: "days_before",
+                    ..
+                })
+            ));
+        }
+    }
+
+    #[test]
+    fn a_mistyped_email_is_refused() {
+        for email in [
+            "asha",
+            "@example.com",
+            "asha@example",
+            "asha@@example.com",
+            "asha@.example.com",
+            "asha@example.com.",
+            "asha sharma@example.com",
+        ] {
+            assert!(
+                matches!(
+                    check_reminder(7, email),
+                    Err(Error::Invalid { field: "email", .. })
+                ),
+                "{email}"
+            );
+        }
+    }
+}
@@ -1,5 +1,8 @@
+use actix_web::http::StatusCode;
 use actix_web::{HttpResponse, ResponseError};
 use thiserror::Error;
+use types::validation::FieldError;
+use types::Problem;
 
 #[derive(Error, Debug)]
 pub enum Error {
@@ -7,22 +10,107 @@ pub enum Error {
     #[error("Generic error: {0}")]
     Generic(String),
 
+    #[error("{0}")]
+    BadRequest(String),
+
+    /// A field that breaks a rule; answered as JSON so clients can point at the field.
+    #[error("{field}: {message}")]
+    Invalid {
+        field: &'static str,
+        message: String,
+    },
+
+    /// An investment that breaks the rules in `types::validation`, every broken one listed.
+    #[error("{}", describe(.0))]
+    Validation(Vec<FieldError>),
+
+    #[error("{0}")]
+    NotFound(String),
+
+    #[error("{0}")]
+    Conflict(String),
+
+    /// An `If-Match` that no longer holds.
+    #[error("{0}")]
+    PreconditionFailed(String),
+
     #[error("database error")]
     Db,
 }
 
+/// `field: message` for each broken rule, on one line.
+fn describe(errors: &[FieldError]) -> String {
+    errors
+        .iter()
+        .map(|error| format!("{}: {}", error.field, error.message))
+        .collect::<Vec<_>>()
+        .join("; ")
+}
+
+/// Content type of every error body.
+pub const PROBLEM_CONTENT_TYPE: &str = "application/problem+json";
+
+impl Error {
+    /// The problem `type` slug and title for this kind of error.
+    fn kind(&self) -> (&'static str, &'static str) {
+        match self {
+            Error::BadRequest(_) => ("bad-request", "Bad request"),
+            Error::Invalid { .. } => ("invalid-field", "Invalid field"),
+            Error::Validation(_) => ("validation", "Validation failed"),
+            Error::NotFound(_) => ("not-found", "Not found"),
+            Error::Conflict(_) => ("conflict", "Conflict"),
+            Error::PreconditionFailed(_) => ("precondition-failed", "Precondition failed"),
+            Error::Db => ("database", "Database error"),
+            Error::Generic(_) => ("internal", "Internal error"),
+        }
+    }
+}
+
 impl ResponseError for Error {
-    fn error_response(&self) -> HttpResponse {
+    fn status_code(&self) -> StatusCode {
         match self {
-            Error::Db => HttpResponse::InternalServerError().body(self.to_string()),
-            Error::Generic(msg) => HttpResponse::InternalServerError().body(msg.clone()),
+            Error::BadRequest(_) | Error::Invalid { .. } => StatusCode::BAD_REQUEST,
+            Error::Validation(_) => StatusCode::UNPROCESSABLE_ENTITY,
+            Error::NotFound(_) => StatusCode::NOT_FOUND,
+            Error::Conflict(_) => StatusCode::CONFLICT,
+            Error::PreconditionFailed(_) => StatusCode::PRECONDITION_FAILED,
+            Error::Db | Error::Generic(_) => StatusCode::INTERNAL_SERVER_ERROR,
         }
     }
+
+    fn error_response(&self) -> HttpResponse {
+        let status = self.status_code();
+        let (kind, title) = self.kind();
+        let (detail, field) = match self {
+            Error::Invalid { field, message } => (message.clone(), Some(field.to_string())),
+            Error::BadRequest(msg)
+            | Error::NotFound(msg)
+            | Error::Conflict(msg)
+            | Error::PreconditionFailed(msg)
+            | Error::Generic(msg) => (msg.clone(), None),
+            Error::Validation(_) | Error::Db => (self.to_string(), None),
+        };
+        let errors = match self {
+            Error::Validation(errors) => errors.clone(),
+            _ => Vec::new(),
+        };
+
+        HttpResponse::build(status)
+            .content_type(PROBLEM_CONTENT_TYPE)
+            .json(Problem {
+                kind: format!("/problems/{kind}"),
+                title: title.to_string(),
+                status: status.as_u16(),
+                detail,
+                field,
+                errors,
+            })
+    }
 }
 
 impl From<surrealdb::Error> for Error {
     fn from(error: surrealdb::Error) -> Self {
-        eprintln!("{error}");
+        log::error!("[{}] {error}", crate::request_id::current());
         Self::Db
     }
 }
@@ -0,0 +1,132 @@
+//! ETags for conditional requests: `If-None-Match` on reads, `If-Match` on updates.
+
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+
+use actix_web::http::header::{self, HeaderName};
+use actix_web::{HttpRequest, HttpResponse};
+
+use crate::prelude::*;
+use types::Investment;
+
+/// An investment's tag, taken from when it was last saved, so every update changes
+/// it, and from its status, which changes on its own when it matures.
+pub fn of_investment(inv: &Investment) -> String {
+    let version = inv
+        .updated_at
+        .and_then(|updated_at| updated_at.timestamp_nanos_opt())
+        .unwrap_or_default();
+    let status = inv.status(chrono::Utc::now());
+
+    format!("\"{version}-{status}\"")
+}
+
+/// A tag for a response body; it changes whenever the body does.
+pub fn of_body(body: &[u8]) -> String {
+    let mut hasher = DefaultHasher::new();
+    body.hash(&mut hasher);
+
+    format!("\"{:x}\"", hasher.finish())
+}
+
+/// Whether `etag` is among the tags the request sends in `name`, `*` matching
+/// any and weak tags matching their strong form. `None` when the header isn't sent.
+pub fn listed(req: &HttpRequest, name: HeaderName, etag: &str) -> Option<bool> {
+    matches(req, name, |tag| tag.trim_start_matches("W/") == etag)
+}
+
+/// Like `listed`, but a weak tag never matches, as `If-Match` requires.
+fn strongly_listed(req: &HttpRequest, name: HeaderName, etag: &str) -> Option<bool> {
+    matches(req, name, |tag| tag == etag)
+}
+
+fn matches(req: &HttpRequest, name: HeaderName, same: impl Fn(&str) -> bool) -> Option<bool> {
+    let tags = req.headers().get(name)?.to_str().unwrap_or_default();
+
+    Some(
+        tags.split(',')
+            .map(str::trim)
+            .any(|tag| tag == "*" || same(tag)),
+    )
+}
+
+/// Refuses an update sent with an `If-Match` that `inv` no longer matches.
+pub fn check_if_match(req: &HttpRequest, inv: &Investment) -> Result<()> {
+    if strongly_listed(req, header::IF_MATCH, &of_investment(inv)) == Some(false) {
+        let id = inv.id.as_ref().map(ToString::to_string).unwrap_or_default();
+        return Err(Error::PreconditionFailed(format!(
+            "Investment {id} has changed since the ETag sent"
+        )));
+    }
+
+    Ok(())
+}
+
+/// `inv` as JSON with its ETag, or 304 Not Modified when the request's
+/// `If-None-Match` already names that tag.
+pub fn investment_response(req: &HttpRequest, inv: &Investment) -> HttpResponse {
+    let etag = of_investment(inv);
+    if listed(req, header::IF_NONE_MATCH, &etag) == Some(true) {
+        return HttpResponse::NotModified()
+            .insert_header((header::ETAG, etag))
+            .finish();
+    }
+
+    HttpResponse::Ok()
+        .insert_header((header::ETAG, etag))
+        .json(inv)
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::test::TestRequest;
+    use chrono::{TimeZone, Utc};
+
+    use super::*;
+
+    fn saved_at(second: u32) -> Investment {
+        Investment {
+            updated_at: Some(Utc.with_ymd_and_hms(2024, 6, 10, 6, 13, second).unwrap()),
+            ..Default::default()
+        }
+    }
+
+    fn request(name: HeaderName, tags: &str) -> HttpRequest {
+        TestRequest::default()
+            .insert_header((name, tags))
+            .to_http_request()
+    }
+
+    #[test]
+    fn the_tag_only_changes_when_the_investment_is_saved_again() {
+        let tag = of_investment(&saved_at(20));
+        let req = request(header::IF_NONE_MATCH, &tag);
+
+        assert_eq!(of_investment(&saved_at(20)), tag);
+        assert_eq!(listed(&req, header::IF_NONE_MATCH, &tag), Some(true));
+        assert_eq!(
+            listed(&req, header::IF_NONE_MATCH, &of_investment(&saved_at(21))),
+            Some(false)
+        );
+    }
+
+    #[test]
+    fn a_weak_tag_matches_a_read_but_not_an_update() {
+        let tag = of_investment(&saved_at(20));
+        let req = request(header::IF_MATCH, &format!("W/{tag}"));
+
+        assert_eq!(listed(&req, header::IF_MATCH, &tag), Some(true));
+        assert_eq!(strongly_listed(&req, header::IF_MATCH, &tag), Some(false));
+        assert_eq!(
+            strongly_listed(&request(header::IF_MATCH, "*"), header::IF_MATCH, &tag),
+            Some(true)
+        );
+    }
+
+    #[test]
+    fn no_header_is_neither_a_match_nor_a_mismatch() {
+        let req = TestRequest::default().to_http_request();
+
+        assert_eq!(listed(&req, header::IF_NONE_MATCH, "\"1\""), None);
+    }
+}
@@ -0,0 +1,78 @@
+//! Pushes investment changes to clients as they happen.
+//!
+//! A SurrealDB LIVE query on the investment table feeds a broadcast channel;
+//! every `/invs/events` connection subscribes to it. If the live query drops
+//! it is reopened after a short delay.
+
+use std::time::Duration;
+
+use actix_web::rt::time::sleep;
+use futures::StreamExt;
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use surrealdb::{Action, Notification};
+use tokio::sync::broadcast;
+
+use crate::cache;
+use crate::db::INVESTMENT;
+use crate::DB;
+use types::Investment;
+
+const RECONNECT_DELAY: Duration = Duration::from_secs(5);
+
+/// Slow subscribers that fall this far behind skip ahead instead of blocking the rest.
+const CHANNEL_CAPACITY: usize = 64;
+
+static CHANGES: Lazy<broadcast::Sender<Change>> =
+    Lazy::new(|| broadcast::channel(CHANNEL_CAPACITY).0);
+
+#[derive(Clone, Debug, Serialize)]
+pub struct Change {
+    /// "create", "update" or "delete".
+    pub action: &'static str,
+    pub investment: Investment,
+}
+
+pub fn subscribe() -> broadcast::Receiver<Change> {
+    CHANGES.subscribe()
+}
+
+/// Runs for the life of the server, forwarding live notifications to subscribers.
+pub async fn forward_changes() {
+    loop {
+        match DB.select::<Vec<Investment>>(INVESTMENT).live().await {
+            Ok(mut stream) => {
+                log::info!("live query on {INVESTMENT} opened");
+                while let Some(notification) = stream.next().await {
+                    match notification {
+                        Ok(notification) => publish(notification),
+                        Err(err) => log::warn!("skipping live notification: {err}"),
+                    }
+                }
+                log::warn!("live query on {INVESTMENT} ended, reopening");
+            }
+            Err(err) => log::error!("could not open live query on {INVESTMENT}: {err}"),
+        }
+
+        sleep(RECONNECT_DELAY).await;
+    }
+}
+
+fn publish(notification: Notification<Investment>) {
+    // writes can come from outside this API too, so drop anything derived from the old data
+    cache::invalidate();
+
+    let action = match notification.action {
+        Action::Create => "create",
+        // deleting only marks the record, so to listeners it is still a delete
+        Action::Update if notification.data.deleted_at.is_some() => "delete",
+        Action::Update => "update",
+        Action::Delete => "delete",
+        _ => return,
+    };
+    // an error only means nobody is listening right now
+    let _ = CHANGES.send(Change {
+        action,
+        investment: notification.data,
+    });
+}
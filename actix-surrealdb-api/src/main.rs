@@ -1,62 +1,171 @@
 mod api;
+mod backup;
+mod cache;
+mod config;
+mod csv;
+mod currency;
 mod db;
 mod error;
+mod etag;
+mod live;
+mod openapi;
 mod prelude;
+mod report;
+mod request_id;
 
 use actix_cors::Cors;
+use actix_web::dev::Service;
+use actix_web::http::header::{HeaderName, HeaderValue};
 use actix_web::middleware::Logger;
-use actix_web::{App, HttpServer};
+use actix_web::{web, App, HttpServer};
 use once_cell::sync::Lazy;
 use surrealdb::engine::remote::ws::{Client, Ws};
 use surrealdb::opt::auth::Root;
 use surrealdb::Surreal;
 
 use api::*;
+use config::{AllowList, CONFIG};
 
 static DB: Lazy<Surreal<Client>> = Lazy::new(Surreal::init);
 
-const PORT: u16 = 8080;
-
 #[actix_web::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
     env_logger::init_from_env(env_logger::Env::new().default_filter_or("info"));
-    DB.connect::<Ws>("localhost:8000").await?;
+    config::log_effective_config();
+
+    DB.connect::<Ws>(CONFIG.db_url.as_str()).await?;
 
     DB.signin(Root {
-        username: "root",
-        password: "root",
+        username: &CONFIG.db_username,
+        password: &CONFIG.db_password,
     })
     .await?;
 
-    DB.use_ns("namespace").use_db("database").await?;
+    DB.use_ns(&CONFIG.db_namespace)
+        .use_db(&CONFIG.db_database)
+        .await?;
 
     log::info!("✅ Database connected successfully!!");
 
-    log::info!("✅ Server running at http://localhost:{PORT}");
+    actix_web::rt::spawn(live::forward_changes());
 
-    HttpServer::new(|| {
-        let cors = Cors::default()
-            .allow_any_origin()
-            .allowed_methods(vec!["GET", "POST", "PATCH", "DELETE"])
-            .allow_any_header()
-            .send_wildcard();
+    log::info!(
+        "✅ Server running at http://{}:{}",
+        CONFIG.host,
+        CONFIG.port
+    );
 
+    HttpServer::new(|| {
         App::new()
-            .wrap(cors)
-            .wrap(Logger::default())
-            .service(create)
-            .service(get)
-            .service(update)
-            .service(delete)
-            .service(list)
+            .wrap_fn(|req, srv| {
+                let id = request_id::from_request(&req);
+                let response = srv.call(req);
+                request_id::scope(id.clone(), async move {
+                    let mut response = response.await?;
+                    if let Ok(value) = HeaderValue::from_str(&id) {
+                        response
+                            .headers_mut()
+                            .insert(HeaderName::from_static("x-request-id"), value);
+                    }
+                    Ok(response)
+                })
+            })
+            .wrap(cors())
+            .wrap(Logger::new(
+                r#"%a "%r" %s %b "%{Referer}i" "%{User-Agent}i" %T %{X-Request-Id}o"#,
+            ))
+            .configure(openapi::configure)
+            .service(web::scope(API_PREFIX).configure(routes))
+            // the unversioned paths the first clients were written against
+            .configure(routes)
     })
-    .bind(("localhost", PORT))?
+    .bind((CONFIG.host.as_str(), CONFIG.port))?
     .run()
     .await?;
 
     Ok(())
 }
 
+/// Cross-origin rules from `CONFIG`. Preflight `OPTIONS` requests are answered
+/// by the middleware itself.
+fn cors() -> Cors {
+    let cors = Cors::default()
+        .expose_headers([
+            TRUNCATED_HEADER,
+            PAGE_SIZE_HEADER,
+            NEXT_CURSOR_HEADER,
+            "ETag",
+            currency::CURRENCY_HEADER,
+            currency::RATE_HEADER,
+            request_id::HEADER,
+        ])
+        .max_age(CONFIG.cors_max_age);
+    let cors = match &CONFIG.cors_origins {
+        AllowList::Any => cors.allow_any_origin().send_wildcard(),
+        AllowList::Only(origins) => origins
+            .iter()
+            .fold(cors, |cors, origin| cors.allowed_origin(origin)),
+    };
+    let cors = match &CONFIG.cors_methods {
+        AllowList::Any => cors.allow_any_method(),
+        AllowList::Only(methods) => cors.allowed_methods(methods.iter().map(String::as_str)),
+    };
+
+    match &CONFIG.cors_headers {
+        AllowList::Any => cors.allow_any_header(),
+        AllowList::Only(headers) => cors.allowed_headers(headers.iter().map(String::as_str)),
+    }
+}
+
+/// Where the current version of the API is served.
+const API_PREFIX: &str = "/api/v1";
+
+/// Every endpoint, registered under `API_PREFIX` and again at the root.
+fn routes(cfg: &mut web::ServiceConfig) {
+    cfg.service(create)
+        .service(get)
+        .service(schedule)
+        .service(view)
+        .service(value_at)
+        .service(what_if)
+        .service(history)
+        .service(update)
+        .service(patch)
+        .service(delete)
+        .service(delete_many)
+        .service(restore)
+        .service(renew)
+        .service(renewals)
+        .service(close)
+        .service(archive)
+        .service(archive_many)
+        .service(unarchive)
+        .service(reminder)
+        .service(due_reminders)
+        .service(reminder_sent)
+        .service(list)
+        .service(list_view)
+        .service(stream)
+        .service(reorder)
+        .service(names)
+        .service(check_duplicate)
+        .service(search)
+        .service(seed)
+        .service(recompute)
+        .service(export)
+        .service(import)
+        .service(batch)
+        .service(events)
+        .service(interest_by_year)
+        .service(allocation)
+        .service(stats)
+        .service(summary)
+        .service(scorecard)
+        .service(blended_maturity)
+        .service(next_maturing)
+        .service(calc_options);
+}
+
 /*
  * ARRANCAR UN CONTENEDOR DOCKER DE SURREALDB CON UN FICHERO docker-compose.yml:
  * sudo docker compose up -d
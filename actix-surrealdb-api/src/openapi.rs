@@ -0,0 +1,120 @@
+//! The OpenAPI description of the investment endpoints, for third-party clients
+//! to discover the API by. Swagger UI serves it too, unless built without the
+//! default `swagger-ui` feature.
+
+use actix_web::web;
+use utoipa::OpenApi;
+#[cfg(feature = "swagger-ui")]
+use utoipa_swagger_ui::SwaggerUi;
+
+use crate::api;
+use types::interest::{
+    CalcOptions, DayCount, FiscalYearInterest, ProjectedValue, RoundingMode, ScheduleEntry, WhatIf,
+};
+use types::validation::FieldError;
+use types::view::{InvestmentView, ScheduleSummary};
+use types::*;
+
+/// Where the document is served; the UI reads it from there too.
+pub const DOCUMENT_PATH: &str = "/api-doc/openapi.json";
+
+#[derive(OpenApi)]
+#[openapi(
+    info(title = "Mone Goblin", description = "Track fixed and recurring deposits."),
+    servers((url = "/api/v1")),
+    paths(
+        api::list,
+        api::search,
+        api::create,
+        api::batch,
+        api::get,
+        api::update,
+        api::patch,
+        api::delete,
+        api::delete_many,
+        api::restore,
+        api::close,
+        api::schedule,
+        api::check_duplicate,
+        api::value_at,
+        api::what_if,
+        api::history,
+        api::view,
+        api::renew,
+        api::renewals,
+        api::archive,
+        api::archive_many,
+        api::unarchive,
+        api::reminder,
+        api::due_reminders,
+        api::reminder_sent,
+        api::list_view,
+        api::reorder,
+        api::seed,
+        api::recompute,
+        api::export,
+        api::import,
+        api::names,
+        api::events,
+        api::stream,
+        api::stats,
+        api::summary,
+        api::interest_by_year,
+        api::allocation,
+        api::scorecard,
+        api::blended_maturity,
+        api::next_maturing,
+        api::calc_options,
+    ),
+    components(schemas(
+        Investment,
+        InvStatus,
+        Lifecycle,
+        BatchItem,
+        AffectedRows,
+        Stats,
+        SummaryGroup,
+        Problem,
+        FieldError,
+        InvestmentView,
+        ScheduleSummary,
+        ScheduleEntry,
+        ProjectedValue,
+        WhatIf,
+        api::WhatIfRequest,
+        AuditEntry,
+        Renewal,
+        Reminder,
+        api::ReminderRequest,
+        RecomputeReport,
+        RecomputeSkipped,
+        RecomputedAmount,
+        Backup,
+        FiscalYearInterest,
+        AllocationSlice,
+        Scorecard,
+        CalcOptions,
+        RoundingMode,
+        DayCount,
+    )),
+    tags(
+        (name = "investments", description = "Create, read, change and delete investments"),
+        (name = "reports", description = "Figures over the whole portfolio"),
+        (name = "reminders", description = "Maturity reminders for an external notifier"),
+        (name = "maintenance", description = "Backups, and fixes for development data"),
+    )
+)]
+struct ApiDoc;
+
+#[cfg(not(feature = "swagger-ui"))]
+async fn document() -> web::Json<utoipa::openapi::OpenApi> {
+    web::Json(ApiDoc::openapi())
+}
+
+/// The document at `DOCUMENT_PATH`, and Swagger UI at `/swagger-ui/` when built in.
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    #[cfg(feature = "swagger-ui")]
+    cfg.service(SwaggerUi::new("/swagger-ui/{_:.*}").url(DOCUMENT_PATH, ApiDoc::openapi()));
+    #[cfg(not(feature = "swagger-ui"))]
+    cfg.route(DOCUMENT_PATH, web::get().to(document));
+}
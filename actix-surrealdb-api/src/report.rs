@@ -0,0 +1,281 @@
+//! Aggregate figures computed across all investments.
+
+use std::collections::BTreeMap;
+
+use chrono::{DateTime, TimeZone, Utc};
+
+use crate::cache;
+use crate::config::CONFIG;
+use crate::db::{get_every_inv, get_held_invs};
+use crate::prelude::*;
+use types::interest::FiscalYearInterest;
+use types::{AllocationSlice, Investment, Scorecard};
+
+/// Interest paid out by Ordinary investments per fiscal year, oldest first, for tax filing.
+pub async fn interest_by_year() -> Result<Vec<FiscalYearInterest>> {
+    cache::cached("interest-by-year", async {
+        let mut totals: BTreeMap<i32, f64> = BTreeMap::new();
+        for inv in get_every_inv().await? {
+            for (year, interest) in inv.interest_by_fiscal_year() {
+                *totals.entry(year).or_default() += interest;
+            }
+        }
+
+        let rounding = CONFIG.calc_options().rounding;
+        let years = totals
+            .into_iter()
+            .map(|(year, interest)| FiscalYearInterest {
+                fiscal_year: format!("{}-{:02}", year, (year + 1) % 100),
+                interest: rounding.apply(interest),
+            })
+            .collect();
+
+        Ok(years)
+    })
+    .await
+}
+
+/// Share of the portfolio held in each investment type, largest first.
+pub async fn allocation() -> Result<Vec<AllocationSlice>> {
+    cache::cached("allocation", async {
+        let mut amounts: BTreeMap<String, i64> = BTreeMap::new();
+        // every investment held, not just the rows the list endpoint would return
+        for inv in get_held_invs().await? {
+            *amounts.entry(inv.inv_type).or_default() += inv.inv_amount;
+        }
+
+        let amounts: Vec<(String, i64)> = amounts.into_iter().collect();
+        let shares = hundredths_of_percent(&amounts);
+        let mut slices: Vec<AllocationSlice> = amounts
+            .into_iter()
+            .zip(shares)
+            .map(|((inv_type, amount), share)| AllocationSlice {
+                inv_type,
+                amount,
+                percent: share as f64 / 100.0,
+            })
+            .collect();
+        slices.sort_by(|a, b| b.amount.cmp(&a.amount));
+
+        Ok(slices)
+    })
+    .await
+}
+
+/// The portfolio's headline figures, built from `allocation` and the investments held.
+pub async fn scorecard() -> Result<Scorecard> {
+    cache::cached("scorecard", async {
+        let options = CONFIG.calc_options();
+        let now = Utc::now();
+        let active = get_held_invs().await?;
+        let slices = allocation().await?;
+
+        let total_invested: i64 = slices.iter().map(|slice| slice.amount).sum();
+        let projected_maturity = active
+            .iter()
+            .map(|inv| inv.maturity_value_with(&options))
+            .sum();
+        let weighted_average_rate = if total_invested > 0 {
+            let weighted: f64 = active
+                .iter()
+                .map(|inv| inv.inv_amount as f64 * inv.return_rate as f64)
+                .sum();
+            // basis points to a percentage, kept to two decimals
+            (weighted / total_invested as f64).round() / 100.0
+        } else {
+            0.0
+        };
+        let mut count_by_type = BTreeMap::new();
+        for inv in &active {
+            *count_by_type.entry(inv.inv_type.clone()).or_default() += 1;
+        }
+
+        Ok(Scorecard {
+            total_invested,
+            projected_maturity,
+            weighted_average_rate,
+            count_by_type,
+            nearest_maturity: active
+                .iter()
+                .filter_map(|inv| inv.end_date)
+                .filter(|end_date| *end_date > now)
+                .min(),
+            diversification: diversification(&slices),
+        })
+    })
+    .await
+}
+
+/// The end date of every investment held still to mature, averaged with
+/// `inv_amount` as the weight. `None` when nothing with an amount is left to mature.
+pub async fn blended_maturity() -> Result<Option<DateTime<Utc>>> {
+    // not cached: which investments count changes as their end dates pass
+    Ok(blended_end_date(&get_held_invs().await?, Utc::now()))
+}
+
+/// `blended_maturity` over `investments` as of `now`.
+fn blended_end_date(investments: &[Investment], now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+    let mut weighted: i128 = 0;
+    let mut total: i128 = 0;
+    for inv in investments {
+        let Some(end_date) = inv.end_date.filter(|end_date| *end_date > now) else {
+            continue;
+        };
+        if inv.inv_amount <= 0 {
+            continue;
+        }
+        weighted += i128::from(end_date.timestamp()) * i128::from(inv.inv_amount);
+        total += i128::from(inv.inv_amount);
+    }
+    if total == 0 {
+        return None;
+    }
+
+    // the average of timestamps lies between them, so it fits back into an i64
+    Utc.timestamp_opt((weighted / total) as i64, 0).single()
+}
+
+/// One minus the sum of squared shares, scaled so an even spread over the
+/// types held scores 1. Two decimals.
+fn diversification(slices: &[AllocationSlice]) -> f64 {
+    if slices.len() < 2 {
+        return 0.0;
+    }
+    let concentration: f64 = slices
+        .iter()
+        .map(|slice| (slice.percent / 100.0).powi(2))
+        .sum();
+    let even = 1.0 - 1.0 / slices.len() as f64;
+    let score = ((1.0 - concentration) / even).clamp(0.0, 1.0);
+
+    (score * 100.0).round() / 100.0
+}
+
+/// Splits 100.00% across `amounts` in hundredths of a percent, handing the
+/// units lost to rounding down to the largest remainders so the total is exact.
+fn hundredths_of_percent(amounts: &[(String, i64)]) -> Vec<i64> {
+    const WHOLE: i64 = 10_000;
+
+    let total: i64 = amounts.iter().map(|(_, amount)| amount).sum();
+    if total <= 0 {
+        return vec![0; amounts.len()];
+    }
+
+    let mut shares: Vec<i64> = Vec::with_capacity(amounts.len());
+    let mut remainders: Vec<(i64, usize)> = Vec::with_capacity(amounts.len());
+    for (index, (_, amount)) in amounts.iter().enumerate() {
+        let scaled = i128::from(*amount) * i128::from(WHOLE);
+        shares.push((scaled / i128::from(total)) as i64);
+        remainders.push(((scaled % i128::from(total)) as i64, index));
+    }
+
+    let leftover = WHOLE - shares.iter().sum::<i64>();
+    remainders.sort_by(|a, b| b.0.cmp(&a.0));
+    for (_, index) in remainders.into_iter().take(leftover.max(0) as usize) {
+        shares[index] += 1;
+    }
+
+    shares
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn amounts(amounts: &[i64]) -> Vec<(String, i64)> {
+        amounts
+            .iter()
+            .enumerate()
+            .map(|(index, amount)| (format!("type {index}"), *amount))
+            .collect()
+    }
+
+    #[test]
+    fn shares_add_up_to_exactly_one_hundred_percent() {
+        let shares = hundredths_of_percent(&amounts(&[1, 1, 1]));
+
+        assert_eq!(shares, [3_334, 3_333, 3_333]);
+        assert_eq!(shares.iter().sum::<i64>(), 10_000);
+    }
+
+    #[test]
+    fn rounding_leftovers_go_to_the_largest_remainder() {
+        let shares = hundredths_of_percent(&amounts(&[2, 1]));
+
+        assert_eq!(shares, [6_667, 3_333]);
+    }
+
+    #[test]
+    fn nothing_invested_has_no_shares() {
+        assert_eq!(hundredths_of_percent(&amounts(&[0, 0])), [0, 0]);
+    }
+
+    fn slices(percents: &[f64]) -> Vec<AllocationSlice> {
+        percents
+            .iter()
+            .enumerate()
+            .map(|(index, percent)| AllocationSlice {
+                inv_type: format!("type {index}"),
+                amount: 0,
+                percent: *percent,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn an_even_spread_scores_one() {
+        assert_eq!(diversification(&slices(&[50.0, 50.0])), 1.0);
+        assert_eq!(diversification(&slices(&[25.0, 25.0, 25.0, 25.0])), 1.0);
+    }
+
+    #[test]
+    fn a_lopsided_spread_scores_less() {
+        assert_eq!(diversification(&slices(&[75.0, 25.0])), 0.75);
+    }
+
+    fn date(year: i32, month: u32, day: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(year, month, day, 0, 0, 0).unwrap()
+    }
+
+    fn maturing(end_date: DateTime<Utc>, inv_amount: i64) -> Investment {
+        Investment {
+            end_date: Some(end_date),
+            inv_amount,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn the_larger_amount_pulls_the_blended_date_towards_it() {
+        let investments = [
+            maturing(date(2025, 1, 1), 300_000),
+            maturing(date(2025, 1, 5), 100_000),
+        ];
+
+        assert_eq!(
+            blended_end_date(&investments, date(2024, 1, 1)),
+            Some(date(2025, 1, 2))
+        );
+    }
+
+    #[test]
+    fn matured_and_empty_investments_do_not_count() {
+        let investments = [
+            maturing(date(2023, 1, 1), 500_000),
+            maturing(date(2025, 1, 1), 100_000),
+            maturing(date(2030, 1, 1), 0),
+        ];
+
+        assert_eq!(
+            blended_end_date(&investments, date(2024, 1, 1)),
+            Some(date(2025, 1, 1))
+        );
+        assert_eq!(blended_end_date(&investments, date(2026, 1, 1)), None);
+    }
+
+    #[test]
+    fn a_single_type_is_not_diversified() {
+        assert_eq!(diversification(&slices(&[100.0])), 0.0);
+        assert_eq!(diversification(&[]), 0.0);
+    }
+}
@@ -0,0 +1,37 @@
+//! Ties log lines to the request that produced them.
+//!
+//! Every request gets an id, taken from its `X-Request-Id` header or
+//! generated, which is echoed back in the response, printed by the access
+//! log and available to any code running for that request via `current`.
+
+use std::future::Future;
+
+use actix_web::dev::ServiceRequest;
+use uuid::Uuid;
+
+pub const HEADER: &str = "X-Request-Id";
+
+tokio::task_local! {
+    static REQUEST_ID: String;
+}
+
+/// The caller's id when it looks sane, otherwise a fresh UUID.
+pub fn from_request(req: &ServiceRequest) -> String {
+    req.headers()
+        .get(HEADER)
+        .and_then(|value| value.to_str().ok())
+        .filter(|id| !id.is_empty() && id.len() <= 128)
+        .map_or_else(|| Uuid::new_v4().to_string(), str::to_string)
+}
+
+/// Runs `future` with `id` as the current request id.
+pub async fn scope<F: Future>(id: String, future: F) -> F::Output {
+    REQUEST_ID.scope(id, future).await
+}
+
+/// The id of the request being handled, or "-" outside of one.
+pub fn current() -> String {
+    REQUEST_ID
+        .try_with(Clone::clone)
+        .unwrap_or_else(|_| "-".to_string())
+}
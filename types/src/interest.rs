@@ -0,0 +1,615 @@
+//! Interest math shared by the API and the web app.
+//!
+//! Culmulative investments compound once a year; Ordinary ones pay the
+//! interest out, so their balance stays at the principal. Any other
+//! `return_type` is logged once and treated as Ordinary. A final partial
+//! year accrues for the share of a year its `DayCount` gives it, Actual/365
+//! unless told otherwise. Fractional amounts are rounded according to
+//! `CalcOptions`, half-up unless told otherwise.
+//!
+//! A term that starts and ends on the same day earns nothing: its maturity
+//! value is the principal and its annualized return is 0.
+
+use std::collections::BTreeSet;
+use std::str::FromStr;
+use std::sync::Mutex;
+
+use chrono::{DateTime, Datelike, Months, NaiveDate, TimeZone, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::Investment;
+
+const DAYS_PER_YEAR: f64 = 365.0;
+
+/// How fractional currency is turned into whole rupees.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default, Deserialize, Serialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(rename_all = "kebab-case")]
+pub enum RoundingMode {
+    /// 2.5 becomes 3, 2.4 becomes 2.
+    #[default]
+    HalfUp,
+    Floor,
+    Ceil,
+}
+
+impl RoundingMode {
+    pub fn apply(self, value: f64) -> i64 {
+        let rounded = match self {
+            RoundingMode::HalfUp => (value + 0.5).floor(),
+            RoundingMode::Floor => value.floor(),
+            RoundingMode::Ceil => value.ceil(),
+        };
+        rounded as i64
+    }
+}
+
+impl FromStr for RoundingMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "half-up" => Ok(RoundingMode::HalfUp),
+            "floor" => Ok(RoundingMode::Floor),
+            "ceil" => Ok(RoundingMode::Ceil),
+            _ => Err(format!("unknown rounding mode: {s}")),
+        }
+    }
+}
+
+/// How the days of a partial year are turned into a fraction of a year.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default, Deserialize, Serialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub enum DayCount {
+    /// Actual days over 365.
+    #[default]
+    #[serde(rename = "actual-365")]
+    Actual365,
+    /// Actual days over 360.
+    #[serde(rename = "actual-360")]
+    Actual360,
+    /// Every month counts as 30 days, over 360 (the US 30/360 rule).
+    #[serde(rename = "30-360")]
+    Thirty360,
+}
+
+impl DayCount {
+    /// The fraction of a year between the two dates.
+    pub fn year_fraction(self, start: DateTime<Utc>, end: DateTime<Utc>) -> f64 {
+        match self {
+            DayCount::Actual365 => (end - start).num_days() as f64 / DAYS_PER_YEAR,
+            DayCount::Actual360 => (end - start).num_days() as f64 / 360.0,
+            DayCount::Thirty360 => {
+                let start_day = start.day().min(30);
+                let end_day = if start_day == 30 {
+                    end.day().min(30)
+                } else {
+                    end.day()
+                };
+                let days = 360 * (end.year() - start.year())
+                    + 30 * (end.month() as i32 - start.month() as i32)
+                    + (end_day as i32 - start_day as i32);
+                days as f64 / 360.0
+            }
+        }
+    }
+}
+
+impl FromStr for DayCount {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "actual-365" => Ok(DayCount::Actual365),
+            "actual-360" => Ok(DayCount::Actual360),
+            "30-360" => Ok(DayCount::Thirty360),
+            _ => Err(format!("unknown day count: {s}")),
+        }
+    }
+}
+
+/// Knobs for the interest math, shared so the API and the web app compute the same figures.
+#[derive(Clone, Copy, PartialEq, Debug, Default, Deserialize, Serialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(default)]
+pub struct CalcOptions {
+    pub rounding: RoundingMode,
+    pub day_count: DayCount,
+}
+
+/// How an investment's interest is paid, read from its `return_type`.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ReturnKind {
+    /// Paid out as it accrues: simple interest.
+    Ordinary,
+    /// Reinvested and paid at maturity: compounded yearly.
+    Cumulative,
+}
+
+/// One year, or the final partial year, of an investment's life.
+#[derive(Clone, PartialEq, Debug, Deserialize, Serialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct ScheduleEntry {
+    pub period_start: DateTime<Utc>,
+    pub period_end: DateTime<Utc>,
+    pub opening_balance: i64,
+    pub interest: i64,
+    pub closing_balance: i64,
+}
+
+/// Maturity figures recomputed for a rate the investment doesn't have, for planning.
+#[derive(Clone, PartialEq, Debug, Deserialize, Serialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct WhatIf {
+    /// The alternate rate, in basis points; a percentage on the wire.
+    #[serde(with = "crate::percent")]
+    #[cfg_attr(feature = "openapi", schema(value_type = f64, example = 8.0))]
+    pub return_rate: i64,
+    pub interest: i64,
+    pub maturity_value: i64,
+}
+
+/// What an investment is projected to be worth on a given day.
+#[derive(Clone, PartialEq, Debug, Deserialize, Serialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct ProjectedValue {
+    pub date: NaiveDate,
+    pub value: i64,
+}
+
+/// Interest paid out in one Indian fiscal year (April to March).
+#[derive(Clone, PartialEq, Debug, Deserialize, Serialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct FiscalYearInterest {
+    /// e.g. "2023-24".
+    pub fiscal_year: String,
+    pub interest: i64,
+}
+
+impl Investment {
+    /// "Culmulative", as the app has always spelled it, and "Cumulative" both
+    /// compound. Anything but those and "Ordinary" falls back to simple
+    /// interest, with a warning the first time each such value is seen,
+    /// rather than guessing.
+    pub fn return_kind(&self) -> ReturnKind {
+        match self.return_type.as_str() {
+            "Culmulative" | "Cumulative" => ReturnKind::Cumulative,
+            "Ordinary" => ReturnKind::Ordinary,
+            other => {
+                warn_unrecognized(other);
+                ReturnKind::Ordinary
+            }
+        }
+    }
+
+    /// True when interest is reinvested instead of being paid out.
+    pub fn is_cumulative(&self) -> bool {
+        self.return_kind() == ReturnKind::Cumulative
+    }
+
+    /// Yearly breakdown from `start_date` to `end_date`, empty when either date is missing.
+    pub fn schedule(&self) -> Vec<ScheduleEntry> {
+        self.schedule_with(&CalcOptions::default())
+    }
+
+    pub fn schedule_with(&self, options: &CalcOptions) -> Vec<ScheduleEntry> {
+        let round = |value: f64| options.rounding.apply(value);
+        let (Some(start), Some(end)) = (self.start_date, self.end_date) else {
+            return Vec::new();
+        };
+
+        let principal = self.inv_amount as f64;
+        let cumulative = self.is_cumulative();
+        let mut balance = principal;
+        let mut entries = Vec::new();
+        let mut period_start = start;
+
+        while period_start < end {
+            let period_end = next_anniversary(period_start).min(end);
+            let years = period_years(period_start, period_end, options.day_count);
+
+            let opening = balance;
+            let interest = if cumulative {
+                let interest = balance * ((1.0 + self.rate()).powf(years) - 1.0);
+                balance += interest;
+                interest
+            } else {
+                principal * self.rate() * years
+            };
+
+            entries.push(ScheduleEntry {
+                period_start,
+                period_end,
+                opening_balance: round(opening),
+                interest: round(interest),
+                closing_balance: round(balance),
+            });
+            period_start = period_end;
+        }
+
+        entries
+    }
+
+    /// Total interest accrued between `start_date` and `end_date`.
+    pub fn interest_earned(&self) -> i64 {
+        self.interest_earned_with(&CalcOptions::default())
+    }
+
+    pub fn interest_earned_with(&self, options: &CalcOptions) -> i64 {
+        options
+            .rounding
+            .apply(self.interest_for(self.term_years(options.day_count)))
+    }
+
+    /// Principal plus all interest accrued by `end_date`.
+    pub fn maturity_value(&self) -> i64 {
+        self.maturity_value_with(&CalcOptions::default())
+    }
+
+    pub fn maturity_value_with(&self, options: &CalcOptions) -> i64 {
+        self.inv_amount + self.interest_earned_with(options)
+    }
+
+    /// Interest and maturity value as if the rate were `return_rate` basis points.
+    pub fn what_if_with(&self, return_rate: i64, options: &CalcOptions) -> WhatIf {
+        let alternate = Investment {
+            return_rate,
+            ..self.clone()
+        };
+
+        WhatIf {
+            return_rate,
+            interest: alternate.interest_earned_with(options),
+            maturity_value: alternate.maturity_value_with(options),
+        }
+    }
+
+    /// Principal plus the interest accrued by `date`: just the principal before
+    /// `start_date`, and the maturity value from `end_date` on.
+    pub fn value_at_with(&self, date: DateTime<Utc>, options: &CalcOptions) -> i64 {
+        let principal = self.inv_amount;
+        let (Some(start), Some(end)) = (self.start_date, self.end_date) else {
+            return principal;
+        };
+
+        if date <= start {
+            principal
+        } else if date >= end {
+            self.maturity_value_with(options)
+        } else {
+            principal
+                + options.rounding.apply(self.interest_for(years_between(
+                    start,
+                    date,
+                    options.day_count,
+                )))
+        }
+    }
+
+    /// `return_amount` less tax at `tax_rate_percent` on the gain. A loss
+    /// isn't taxed, and a zero rate gives `return_amount` back unchanged.
+    pub fn net_return(&self, tax_rate_percent: f64) -> i64 {
+        let gross = self.return_amount;
+        let gain = gross - self.inv_amount;
+        if gain <= 0 {
+            return gross;
+        }
+
+        gross - RoundingMode::HalfUp.apply(gain as f64 * tax_rate_percent / 100.0)
+    }
+
+    /// Interest over the whole term as a percentage of the principal, to two decimals.
+    pub fn roi_percent_with(&self, options: &CalcOptions) -> f64 {
+        if self.inv_amount == 0 {
+            return 0.0;
+        }
+        let roi = self.interest_earned_with(options) as f64 / self.inv_amount as f64 * 100.0;
+
+        (roi * 100.0).round() / 100.0
+    }
+
+    /// Compound yearly growth from `inv_amount` to `return_amount`, as a
+    /// percentage to two decimals. 0 where it isn't defined: a zero-day term,
+    /// no principal or no return.
+    pub fn annualized_return(&self) -> f64 {
+        let years = self.term_years(DayCount::default());
+        if years <= 0.0 || self.inv_amount <= 0 || self.return_amount <= 0 {
+            return 0.0;
+        }
+        let growth = self.return_amount as f64 / self.inv_amount as f64;
+        let annualized = (growth.powf(1.0 / years) - 1.0) * 100.0;
+
+        (annualized * 100.0).round() / 100.0
+    }
+
+    /// Interest accrued in each fiscal year the investment is active, keyed by the
+    /// calendar year the fiscal year starts in and pro-rated by days. Culmulative
+    /// investments pay nothing out until maturity, so they have no entries.
+    pub fn interest_by_fiscal_year(&self) -> Vec<(i32, f64)> {
+        let (Some(start), Some(end)) = (self.start_date, self.end_date) else {
+            return Vec::new();
+        };
+        if self.is_cumulative() {
+            return Vec::new();
+        }
+
+        let principal = self.inv_amount as f64;
+        let mut entries = Vec::new();
+        let mut year = fiscal_year_of(start);
+        while fiscal_year_start(year) < end {
+            let from = start.max(fiscal_year_start(year));
+            let to = end.min(fiscal_year_start(year + 1));
+            if to > from {
+                let days = (to - from).num_days() as f64;
+                entries.push((year, principal * self.rate() * days / DAYS_PER_YEAR));
+            }
+            year += 1;
+        }
+
+        entries
+    }
+
+    fn rate(&self) -> f64 {
+        // basis points to a fraction
+        self.return_rate as f64 / 10_000.0
+    }
+
+    fn interest_for(&self, years: f64) -> f64 {
+        let principal = self.inv_amount as f64;
+        if self.is_cumulative() {
+            principal * ((1.0 + self.rate()).powf(years) - 1.0)
+        } else {
+            principal * self.rate() * years
+        }
+    }
+
+    /// Length of the investment in years: whole anniversaries plus the partial remainder.
+    fn term_years(&self, day_count: DayCount) -> f64 {
+        let (Some(start), Some(end)) = (self.start_date, self.end_date) else {
+            return 0.0;
+        };
+
+        years_between(start, end, day_count)
+    }
+}
+
+/// Whole anniversaries between the two dates plus the partial remainder.
+fn years_between(start: DateTime<Utc>, end: DateTime<Utc>, day_count: DayCount) -> f64 {
+    let mut years = 0.0;
+    let mut period_start = start;
+    while period_start < end {
+        let period_end = next_anniversary(period_start).min(end);
+        years += period_years(period_start, period_end, day_count);
+        period_start = period_end;
+    }
+
+    years
+}
+
+fn next_anniversary(date: DateTime<Utc>) -> DateTime<Utc> {
+    date.checked_add_months(Months::new(12))
+        .unwrap_or(DateTime::<Utc>::MAX_UTC)
+}
+
+/// A full anniversary counts as exactly one year, anything shorter by `day_count`.
+fn period_years(start: DateTime<Utc>, end: DateTime<Utc>, day_count: DayCount) -> f64 {
+    if end == next_anniversary(start) {
+        1.0
+    } else {
+        day_count.year_fraction(start, end)
+    }
+}
+
+/// Fiscal years run April to March and are named after the year they start in.
+/// `return_type` values already warned about, so a list of many such
+/// investments, each computed several times, logs each value once.
+static WARNED_RETURN_TYPES: Mutex<BTreeSet<String>> = Mutex::new(BTreeSet::new());
+
+fn warn_unrecognized(return_type: &str) {
+    let Ok(mut warned) = WARNED_RETURN_TYPES.lock() else {
+        return;
+    };
+    if warned.insert(return_type.to_string()) {
+        log::warn!("unrecognized return_type {return_type:?}, using simple interest");
+    }
+}
+
+fn fiscal_year_of(date: DateTime<Utc>) -> i32 {
+    if date.month() >= 4 {
+        date.year()
+    } else {
+        date.year() - 1
+    }
+}
+
+fn fiscal_year_start(year: i32) -> DateTime<Utc> {
+    Utc.with_ymd_and_hms(year, 4, 1, 0, 0, 0)
+        .single()
+        .unwrap_or(DateTime::<Utc>::MAX_UTC)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(year: i32, month: u32, day: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(year, month, day, 0, 0, 0).unwrap()
+    }
+
+    fn investment(return_type: &str, start: DateTime<Utc>, end: DateTime<Utc>) -> Investment {
+        Investment {
+            inv_name: "SBI".into(),
+            inv_type: "FD".into(),
+            return_rate: 700,
+            return_type: return_type.into(),
+            inv_amount: 100_000,
+            return_amount: 121_000,
+            name: "Asha".into(),
+            start_date: Some(start),
+            end_date: Some(end),
+            ..Default::default()
+        }
+    }
+
+    fn assert_close(actual: f64, expected: f64) {
+        assert!((actual - expected).abs() < 1e-12, "{actual} != {expected}");
+    }
+
+    #[test]
+    fn each_rounding_mode_on_a_half() {
+        assert_eq!(RoundingMode::HalfUp.apply(2.5), 3);
+        assert_eq!(RoundingMode::Floor.apply(2.5), 2);
+        assert_eq!(RoundingMode::Ceil.apply(2.5), 3);
+    }
+
+    #[test]
+    fn schedule_of_a_clean_three_year_term() {
+        let ordinary = investment("Ordinary", date(2021, 1, 1), date(2024, 1, 1));
+        let interest: Vec<i64> = ordinary.schedule().iter().map(|e| e.interest).collect();
+        assert_eq!(interest, [7_000, 7_000, 7_000]);
+
+        let cumulative = investment("Culmulative", date(2021, 1, 1), date(2024, 1, 1));
+        let closing: Vec<i64> = cumulative
+            .schedule()
+            .iter()
+            .map(|e| e.closing_balance)
+            .collect();
+        assert_eq!(closing, [107_000, 114_490, 122_504]);
+    }
+
+    #[test]
+    fn schedule_ends_on_a_partial_year() {
+        let schedule = investment("Ordinary", date(2021, 1, 1), date(2022, 4, 1)).schedule();
+
+        assert_eq!(schedule.len(), 2);
+        assert_eq!(schedule[1].period_start, date(2022, 1, 1));
+        assert_eq!(schedule[1].period_end, date(2022, 4, 1));
+        // 90 days of 7% on 1,00,000
+        assert_eq!(schedule[1].interest, 1_726);
+    }
+
+    #[test]
+    fn each_day_count_convention() {
+        let (start, end) = (date(2021, 1, 31), date(2021, 3, 1));
+
+        assert_close(DayCount::Actual365.year_fraction(start, end), 29.0 / 365.0);
+        assert_close(DayCount::Actual360.year_fraction(start, end), 29.0 / 360.0);
+        // the 31st counts as the 30th, so two months less 29 days
+        assert_close(DayCount::Thirty360.year_fraction(start, end), 31.0 / 360.0);
+    }
+
+    #[test]
+    fn culmulative_compounds_and_unknown_types_do_not() {
+        let (start, end) = (date(2021, 1, 1), date(2023, 1, 1));
+
+        assert_eq!(
+            investment("Culmulative", start, end).return_kind(),
+            ReturnKind::Cumulative
+        );
+        assert_eq!(
+            investment("Culmulative", start, end).maturity_value(),
+            114_490
+        );
+        assert_eq!(
+            investment("Bonus", start, end).return_kind(),
+            ReturnKind::Ordinary
+        );
+        assert_eq!(investment("Bonus", start, end).maturity_value(), 114_000);
+    }
+
+    #[test]
+    fn a_zero_day_term_earns_nothing() {
+        let same_day = investment("Culmulative", date(2024, 1, 1), date(2024, 1, 1));
+
+        assert!(same_day.schedule().is_empty());
+        assert_eq!(same_day.maturity_value(), same_day.inv_amount);
+        assert_eq!(same_day.annualized_return(), 0.0);
+    }
+
+    #[test]
+    fn net_return_taxes_only_the_gain() {
+        let mut inv = investment("Ordinary", date(2021, 1, 1), date(2024, 1, 1));
+        inv.return_amount = 120_000;
+        assert_eq!(inv.net_return(30.0), 114_000);
+        assert_eq!(inv.net_return(0.0), 120_000);
+
+        inv.return_amount = 90_000;
+        assert_eq!(inv.net_return(30.0), 90_000);
+    }
+
+    #[test]
+    fn what_if_recomputes_at_the_other_rate() {
+        let inv = investment("Ordinary", date(2021, 1, 1), date(2024, 1, 1));
+        let what_if = inv.what_if_with(800, &CalcOptions::default());
+
+        assert_eq!(what_if.return_rate, 800);
+        assert_eq!(what_if.interest, 24_000);
+        assert_eq!(what_if.maturity_value, 124_000);
+    }
+
+    #[test]
+    fn value_part_way_through_the_term() {
+        let inv = investment("Ordinary", date(2021, 1, 1), date(2024, 1, 1));
+
+        assert_eq!(
+            inv.value_at_with(date(2022, 1, 1), &CalcOptions::default()),
+            107_000
+        );
+    }
+
+    #[test]
+    fn value_after_maturity_stays_at_the_maturity_value() {
+        let inv = investment("Culmulative", date(2021, 1, 1), date(2024, 1, 1));
+        let options = CalcOptions::default();
+
+        assert_eq!(
+            inv.value_at_with(date(2030, 1, 1), &options),
+            inv.maturity_value_with(&options)
+        );
+    }
+
+    #[test]
+    fn value_before_the_start_is_the_principal() {
+        let inv = investment("Ordinary", date(2021, 1, 1), date(2024, 1, 1));
+
+        assert_eq!(
+            inv.value_at_with(date(2020, 6, 1), &CalcOptions::default()),
+            100_000
+        );
+    }
+
+    #[test]
+    fn a_term_within_one_fiscal_year() {
+        let inv = investment("Ordinary", date(2021, 4, 1), date(2022, 4, 1));
+        let years = inv.interest_by_fiscal_year();
+
+        assert_eq!(years.len(), 1);
+        assert_eq!(years[0].0, 2021);
+        assert_close(years[0].1, 7_000.0);
+    }
+
+    #[test]
+    fn a_term_across_fiscal_years_is_split_by_days() {
+        let inv = investment("Ordinary", date(2021, 1, 1), date(2022, 7, 1));
+        let years = inv.interest_by_fiscal_year();
+
+        assert_eq!(years.len(), 3);
+        // 90 days to 1 April, a full year, then 91 days
+        assert_eq!(years[0].0, 2020);
+        assert_close(years[0].1, 7_000.0 * 90.0 / 365.0);
+        assert_eq!(years[1].0, 2021);
+        assert_close(years[1].1, 7_000.0);
+        assert_eq!(years[2].0, 2022);
+        assert_close(years[2].1, 7_000.0 * 91.0 / 365.0);
+    }
+
+    #[test]
+    fn a_term_starting_on_1_april_belongs_to_that_fiscal_year() {
+        let inv = investment("Ordinary", date(2022, 4, 1), date(2023, 1, 1));
+        let years = inv.interest_by_fiscal_year();
+
+        assert_eq!(years.len(), 1);
+        assert_eq!(years[0].0, 2022);
+        assert_close(years[0].1, 7_000.0 * 275.0 / 365.0);
+    }
+}
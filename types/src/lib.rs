@@ -1,32 +1,345 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::str::FromStr;
+
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use surrealdb::sql::Thing;
 
-#[derive(Clone, PartialEq, Debug, Deserialize, Serialize)]
+pub mod interest;
+pub mod validation;
+pub mod view;
+
+#[derive(Clone, PartialEq, Debug, Default, Deserialize, Serialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 pub struct Investment {
+    #[cfg_attr(feature = "openapi", schema(value_type = Option<Object>))]
     pub id: Option<Thing>,
     pub inv_name: String,
     pub inv_type: String,
-    pub return_rate: i32,
+    /// Annual rate in basis points (725 is 7.25%). On the wire and in the
+    /// database it is a percentage, e.g. `7.25`, as it always was.
+    #[serde(with = "percent")]
+    #[cfg_attr(feature = "openapi", schema(value_type = f64, example = 7.25))]
+    pub return_rate: i64,
     pub return_type: String,
-    pub inv_amount: i32,
-    pub return_amount: i32,
+    /// Amounts are whole rupees held as `i64`, so crores are well within range.
+    pub inv_amount: i64,
+    pub return_amount: i64,
     pub name: String,
     pub inv_status: Option<InvStatus>,
     pub start_date: Option<DateTime<Utc>>,
     pub end_date: Option<DateTime<Utc>>,
     pub created_at: Option<DateTime<Utc>>,
     pub updated_at: Option<DateTime<Utc>>,
+    /// Hidden from the default list without being deleted.
+    #[serde(default)]
+    pub archived: bool,
+    /// Id of the investment this one was renewed from.
+    #[serde(default)]
+    pub renewed_from: Option<String>,
+    /// Id of the investment this one was renewed into, once it has been.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub renewed_to: Option<String>,
+    /// Position in the user's manual ordering; lower comes first.
+    #[serde(default)]
+    pub sort_order: i64,
+    /// RD only: the amount deposited every month.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub monthly_amount: Option<i64>,
+    /// RD only: how many monthly deposits there are.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub installments: Option<i32>,
+    /// Days from `start_date` during which closing early may cost a penalty.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub lock_in_days: Option<u32>,
+    /// When it was deleted. Deleted investments are kept, out of sight, until restored.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub deleted_at: Option<DateTime<Utc>>,
+    /// Where it is in its life as of the response. Never stored: it is worked
+    /// out on the way out with `status(now)` and ignored when sent back.
+    #[serde(default, skip_deserializing, skip_serializing_if = "Option::is_none")]
+    pub status: Option<Lifecycle>,
 }
 
 #[derive(Clone, PartialEq, Debug, Deserialize, Serialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 pub struct InvStatus {
+    #[cfg_attr(feature = "openapi", schema(value_type = Option<Object>))]
     pub id: Option<Thing>,
     pub status: String,
 }
 
+/// Where an investment is in its life. Active and matured follow from the
+/// dates; closed and renewed are recorded in `inv_status` and are final.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Deserialize, Serialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(rename_all = "lowercase")]
+pub enum Lifecycle {
+    Active,
+    Matured,
+    Closed,
+    Renewed,
+}
+
+impl Lifecycle {
+    pub const ALL: [Lifecycle; 4] = [
+        Lifecycle::Active,
+        Lifecycle::Matured,
+        Lifecycle::Closed,
+        Lifecycle::Renewed,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Lifecycle::Active => "active",
+            Lifecycle::Matured => "matured",
+            Lifecycle::Closed => "closed",
+            Lifecycle::Renewed => "renewed",
+        }
+    }
+
+    /// Whether an investment in this state may move to `next`. Closed and
+    /// renewed are final; active and matured may become anything else, e.g.
+    /// matured turns active again when the end date is moved on.
+    pub fn can_become(self, next: Lifecycle) -> bool {
+        next != self && matches!(self, Lifecycle::Active | Lifecycle::Matured)
+    }
+}
+
+impl fmt::Display for Lifecycle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for Lifecycle {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Lifecycle::ALL
+            .into_iter()
+            .find(|status| status.as_str().eq_ignore_ascii_case(s.trim()))
+            .ok_or_else(|| format!("unknown status \"{s}\""))
+    }
+}
+
 #[derive(Debug, Deserialize, Serialize)]
 pub struct Record {
     #[allow(dead_code)]
     pub id: Thing,
 }
+
+/// Both sides of a renewal: the original, now closed, and its successor.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct Renewal {
+    pub closed: Investment,
+    pub renewed: Investment,
+}
+
+/// One investment type's share of the total invested.
+#[derive(Clone, PartialEq, Debug, Deserialize, Serialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct AllocationSlice {
+    pub inv_type: String,
+    pub amount: i64,
+    /// Two decimals; the slices of a portfolio always add up to exactly 100.
+    pub percent: f64,
+}
+
+/// Headline figures for the active portfolio. All zero, and no maturity date, when it is empty.
+#[derive(Clone, PartialEq, Debug, Default, Deserialize, Serialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct Scorecard {
+    pub total_invested: i64,
+    pub projected_maturity: i64,
+    /// Annual rate weighted by amount invested, as a percentage to two decimals.
+    pub weighted_average_rate: f64,
+    pub count_by_type: BTreeMap<String, u64>,
+    pub nearest_maturity: Option<DateTime<Utc>>,
+    /// How evenly the money is spread across types: 0 when it is all in one,
+    /// 1 when every type holds the same amount.
+    pub diversification: f64,
+}
+
+/// Totals over the investments still held.
+#[derive(Clone, PartialEq, Debug, Default, Deserialize, Serialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct Stats {
+    pub total_invested: i64,
+    /// Sum of every `return_amount`, i.e. what the portfolio pays back at maturity.
+    pub total_return: i64,
+    pub count_by_type: BTreeMap<String, u64>,
+    /// Annual rate weighted by amount invested, as a percentage to two decimals.
+    pub weighted_average_rate: f64,
+}
+
+/// Totals for one person, investment name or type in `GET /summary`.
+#[derive(Clone, PartialEq, Debug, Deserialize, Serialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct SummaryGroup {
+    /// The value grouped on, e.g. the investor's name.
+    pub key: String,
+    pub count: u64,
+    pub invested: i64,
+    pub returns: i64,
+}
+
+/// The state an investment was in before an update or delete changed it.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct AuditEntry {
+    /// Id of the investment, e.g. `investment:abc`.
+    pub investment: String,
+    /// `update` or `delete`.
+    pub action: String,
+    pub previous: Investment,
+    pub recorded_at: DateTime<Utc>,
+}
+
+/// A stored `return_amount` that differs from what the rate and dates give.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct RecomputedAmount {
+    /// Id of the investment, e.g. `investment:abc`.
+    pub investment: String,
+    pub stored: i64,
+    pub computed: i64,
+}
+
+/// An investment a recompute left as it was, because writing it back failed.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct RecomputeSkipped {
+    /// Id of the investment, e.g. `investment:abc`.
+    pub investment: String,
+    pub reason: String,
+}
+
+/// What a recompute found and, unless it was a dry run, wrote back.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct RecomputeReport {
+    pub dry_run: bool,
+    /// Investments with both dates, the ones a maturity value can be computed for.
+    pub checked: u64,
+    pub changed: Vec<RecomputedAmount>,
+    /// Sum of `computed - stored` over the changes.
+    pub total_difference: i64,
+    /// Differing investments that would not save, e.g. for failing validation;
+    /// they are in neither `changed` nor `total_difference`.
+    #[serde(default)]
+    pub skipped: Vec<RecomputeSkipped>,
+}
+
+/// A request to be emailed `days_before` an investment matures.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct Reminder {
+    #[cfg_attr(feature = "openapi", schema(value_type = Option<Object>))]
+    pub id: Option<Thing>,
+    /// Id of the investment, e.g. `investment:abc`.
+    pub investment: String,
+    pub days_before: i64,
+    pub email: String,
+    /// The maturity date less `days_before`; the reminder is due from then on.
+    pub due_at: DateTime<Utc>,
+    pub created_at: DateTime<Utc>,
+    /// When the notifier delivered it; a sent reminder is never due again.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sent_at: Option<DateTime<Utc>>,
+}
+
+/// The backup format `GET /invs/export.json` writes, in its current version.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct Backup {
+    /// `BACKUP_VERSION` when written; older versions are migrated on import.
+    pub version: u32,
+    pub investments: Vec<Investment>,
+}
+
+pub const BACKUP_VERSION: u32 = 1;
+
+/// What became of one investment sent to `POST /invs/batch`, in the order sent:
+/// the id it was created with, or why it was refused.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct BatchItem {
+    #[cfg_attr(feature = "openapi", schema(value_type = Option<Object>))]
+    pub id: Option<Thing>,
+    pub error: Option<String>,
+}
+
+/// An error as the API reports it: an RFC 7807 problem document, sent as
+/// `application/problem+json`.
+#[derive(Clone, PartialEq, Debug, Deserialize, Serialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct Problem {
+    /// What kind of problem it is, e.g. `/problems/not-found`; stable for clients to match on.
+    #[serde(rename = "type")]
+    pub kind: String,
+    pub title: String,
+    pub status: u16,
+    /// What went wrong this time, fit to show the user.
+    pub detail: String,
+    /// The field at fault, when a single rule was broken.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub field: Option<String>,
+    /// Every rule an investment broke, when it failed validation.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub errors: Vec<validation::FieldError>,
+}
+
+/// How many records a write touched, e.g. 0 when deleting an id that didn't exist.
+#[derive(Debug, Deserialize, Serialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct AffectedRows {
+    pub rows_affected: u64,
+}
+
+/// (De)serializes a basis-point rate as a percentage, so 725 is written as `7.25`.
+/// A percentage with more than two decimals has no basis-point value and is rejected.
+pub(crate) mod percent {
+    use serde::de::Error;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(basis_points: &i64, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_f64(*basis_points as f64 / 100.0)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<i64, D::Error> {
+        let hundredths = f64::deserialize(deserializer)? * 100.0;
+        // 8.2 * 100.0 is 819.9999999999999, so only a real third decimal counts
+        if (hundredths - hundredths.round()).abs() > 1e-6 {
+            return Err(D::Error::custom(
+                "a rate has at most two decimals, e.g. 7.25",
+            ));
+        }
+
+        Ok(hundredths.round() as i64)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, Deserialize, Serialize)]
+    struct Rate(#[serde(with = "percent")] i64);
+
+    #[test]
+    fn a_percentage_reads_as_basis_points_and_back() {
+        let rate: Rate = serde_json::from_str("7.25").unwrap();
+
+        assert_eq!(rate.0, 725);
+        assert_eq!(serde_json::to_string(&rate).unwrap(), "7.25");
+    }
+
+    #[test]
+    fn a_third_decimal_is_rejected() {
+        assert!(serde_json::from_str::<Rate>("7.125").is_err());
+        assert_eq!(serde_json::from_str::<Rate>("8.2").unwrap().0, 820);
+    }
+}
@@ -0,0 +1,295 @@
+use chrono::{DateTime, Datelike, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::Investment;
+
+/// Earliest year accepted for an investment date.
+pub const MIN_YEAR: i32 = 1950;
+
+/// Latest year accepted for an investment date.
+pub const MAX_YEAR: i32 = 2100;
+
+/// Returns true when the date falls inside the accepted `MIN_YEAR..=MAX_YEAR` window.
+///
+/// Catches typos like year 0202 or 20205 that still parse as valid dates.
+pub fn is_reasonable_date(date: &DateTime<Utc>) -> bool {
+    (MIN_YEAR..=MAX_YEAR).contains(&date.year())
+}
+
+/// How far, in percent, an entered return amount may stray from the computed
+/// maturity value before it is flagged.
+pub const RETURN_TOLERANCE_PERCENT: f64 = 5.0;
+
+/// The computed maturity value when `return_amount` is more than
+/// `RETURN_TOLERANCE_PERCENT` away from it. `None` when the two agree or when
+/// an input needed for the computation is still missing.
+///
+/// This is a hint, not a rule: banks round and compound in their own ways.
+pub fn return_amount_mismatch(investment: &Investment) -> Option<i64> {
+    let (Some(start), Some(end)) = (investment.start_date, investment.end_date) else {
+        return None;
+    };
+    if end <= start
+        || investment.inv_amount <= 0
+        || investment.return_amount <= 0
+        || investment.return_rate <= 0
+        || investment.return_type.is_empty()
+    {
+        return None;
+    }
+
+    let expected = investment.maturity_value();
+    let deviation = (investment.return_amount - expected).abs() as f64;
+    (deviation / expected as f64 * 100.0 > RETURN_TOLERANCE_PERCENT).then_some(expected)
+}
+
+/// Highest annual rate accepted, in basis points (100%).
+pub const MAX_RATE: i64 = 10_000;
+
+/// A rule an investment breaks, named by the field on the wire it concerns.
+#[derive(Clone, PartialEq, Debug, Deserialize, Serialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct FieldError {
+    pub field: String,
+    pub message: String,
+}
+
+/// Every rule `investment` breaks, in field order; empty when it may be saved.
+/// The API applies these to every write, so they hold whatever the client.
+pub fn validate(investment: &Investment) -> Vec<FieldError> {
+    check(investment, None)
+}
+
+/// `validate` for a change to `stored`. A date `stored` never had may stay
+/// missing, so records saved before dates were required can still be edited
+/// and archived.
+pub fn validate_update(investment: &Investment, stored: &Investment) -> Vec<FieldError> {
+    check(investment, Some(stored))
+}
+
+/// `validate` for a record saved before, e.g. one read back from a backup:
+/// as with `validate_update`, dates it never had may stay missing.
+pub fn validate_existing(investment: &Investment) -> Vec<FieldError> {
+    check(investment, Some(investment))
+}
+
+fn check(investment: &Investment, stored: Option<&Investment>) -> Vec<FieldError> {
+    let mut errors = Vec::new();
+    let mut report = |field: &str, message: String| {
+        errors.push(FieldError {
+            field: field.to_string(),
+            message,
+        })
+    };
+
+    for (field, value) in [
+        ("inv_name", &investment.inv_name),
+        ("inv_type", &investment.inv_type),
+        ("return_type", &investment.return_type),
+        ("name", &investment.name),
+    ] {
+        if value.trim().is_empty() {
+            report(field, "must not be blank".to_string());
+        }
+    }
+
+    if !(1..=MAX_RATE).contains(&investment.return_rate) {
+        report(
+            "return_rate",
+            format!("must be more than 0 and at most {}%", MAX_RATE / 100),
+        );
+    }
+    if investment.inv_amount <= 0 {
+        report("inv_amount", "must be more than 0".to_string());
+    }
+    if investment.return_amount <= 0 {
+        report("return_amount", "must be more than 0".to_string());
+    } else if investment.return_amount < investment.inv_amount {
+        report(
+            "return_amount",
+            "must not be less than inv_amount".to_string(),
+        );
+    }
+
+    for (field, date, stored_date) in [
+        (
+            "start_date",
+            investment.start_date,
+            stored.map(|stored| stored.start_date),
+        ),
+        (
+            "end_date",
+            investment.end_date,
+            stored.map(|stored| stored.end_date),
+        ),
+    ] {
+        match date {
+            None if stored_date == Some(None) => {}
+            None => report(field, "is required".to_string()),
+            Some(date) if !is_reasonable_date(&date) => {
+                report(field, format!("must be between {MIN_YEAR} and {MAX_YEAR}"))
+            }
+            Some(_) => {}
+        }
+    }
+    // a term starting and ending on the same day is allowed; it earns nothing
+    if let (Some(start), Some(end)) = (investment.start_date, investment.end_date) {
+        if end < start {
+            report("end_date", "must not be before start_date".to_string());
+        }
+    }
+
+    // the RD fields come as a pair of positive numbers and only on an RD; an
+    // RD stored before they existed may leave both out
+    let is_rd = investment.inv_type == "RD";
+    let any_given = investment.monthly_amount.is_some() || investment.installments.is_some();
+    for (field, value) in [
+        ("monthly_amount", investment.monthly_amount),
+        ("installments", investment.installments.map(i64::from)),
+    ] {
+        let message = match value {
+            Some(_) if !is_rd => "only applies to an RD",
+            Some(value) if value <= 0 => "must be positive",
+            None if is_rd && any_given => "is required for an RD",
+            _ => continue,
+        };
+        report(field, message.to_string());
+    }
+
+    errors
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+
+    fn date(year: i32, month: u32, day: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(year, month, day, 0, 0, 0).unwrap()
+    }
+
+    fn fd() -> Investment {
+        Investment {
+            inv_name: "SBI".into(),
+            inv_type: "FD".into(),
+            return_rate: 700,
+            return_type: "Ordinary".into(),
+            inv_amount: 100_000,
+            return_amount: 121_000,
+            name: "Asha".into(),
+            start_date: Some(date(2023, 4, 1)),
+            end_date: Some(date(2026, 4, 1)),
+            ..Default::default()
+        }
+    }
+
+    fn fields(errors: &[FieldError]) -> Vec<&str> {
+        errors.iter().map(|error| error.field.as_str()).collect()
+    }
+
+    #[test]
+    fn mistyped_years_are_not_reasonable() {
+        assert!(!is_reasonable_date(&date(202, 1, 15)));
+        assert!(!is_reasonable_date(&date(20205, 1, 15)));
+    }
+
+    #[test]
+    fn a_normal_date_is_reasonable() {
+        assert!(is_reasonable_date(&date(2024, 6, 30)));
+    }
+
+    #[test]
+    fn a_complete_investment_is_valid() {
+        assert!(validate(&fd()).is_empty());
+    }
+
+    #[test]
+    fn dates_out_of_range_are_reported() {
+        let investment = Investment {
+            start_date: Some(date(202, 4, 1)),
+            end_date: Some(date(20205, 4, 1)),
+            ..fd()
+        };
+
+        assert_eq!(fields(&validate(&investment)), ["start_date", "end_date"]);
+    }
+
+    #[test]
+    fn a_zero_day_term_is_valid() {
+        let investment = Investment {
+            end_date: Some(date(2023, 4, 1)),
+            return_amount: 100_000,
+            ..fd()
+        };
+
+        assert!(validate(&investment).is_empty());
+    }
+
+    #[test]
+    fn dates_may_stay_missing_only_when_they_were_never_stored() {
+        let undated = Investment {
+            start_date: None,
+            end_date: None,
+            ..fd()
+        };
+
+        assert!(validate_update(&undated, &undated).is_empty());
+        assert_eq!(
+            fields(&validate_update(&undated, &fd())),
+            ["start_date", "end_date"]
+        );
+        assert_eq!(fields(&validate(&undated)), ["start_date", "end_date"]);
+    }
+
+    #[test]
+    fn a_return_amount_matching_the_rate_is_not_flagged() {
+        assert_eq!(return_amount_mismatch(&fd()), None);
+    }
+
+    #[test]
+    fn a_return_amount_far_from_the_rate_is_flagged_with_the_computed_one() {
+        let investment = Investment {
+            return_amount: 150_000,
+            ..fd()
+        };
+
+        assert_eq!(return_amount_mismatch(&investment), Some(121_000));
+    }
+
+    fn rd() -> Investment {
+        Investment {
+            inv_type: "RD".into(),
+            inv_amount: 120_000,
+            return_amount: 128_000,
+            monthly_amount: Some(10_000),
+            installments: Some(12),
+            ..fd()
+        }
+    }
+
+    #[test]
+    fn a_complete_rd_is_valid() {
+        assert!(validate(&rd()).is_empty());
+    }
+
+    #[test]
+    fn an_rd_needs_both_of_its_fields() {
+        let investment = Investment {
+            installments: None,
+            ..rd()
+        };
+
+        assert_eq!(fields(&validate(&investment)), ["installments"]);
+    }
+
+    #[test]
+    fn an_fd_takes_no_rd_fields() {
+        let investment = Investment {
+            monthly_amount: Some(10_000),
+            ..fd()
+        };
+
+        assert_eq!(fields(&validate(&investment)), ["monthly_amount"]);
+    }
+}
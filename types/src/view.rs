@@ -0,0 +1,208 @@
+//! Read-only projections of an investment with derived figures filled in.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::interest::{CalcOptions, ScheduleEntry};
+use crate::{Investment, Lifecycle};
+
+/// An investment as listed, with the figures the UI would otherwise recompute per row.
+#[derive(Clone, PartialEq, Debug, Deserialize, Serialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct InvestmentView {
+    #[serde(flatten)]
+    pub investment: Investment,
+    pub maturity_value: i64,
+    pub roi_percent: f64,
+    /// Negative once the investment has matured, `None` without an end date.
+    pub days_to_maturity: Option<i64>,
+    pub status: Lifecycle,
+    /// Only filled in when a single investment is fetched, to keep lists light.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub schedule_summary: Option<ScheduleSummary>,
+}
+
+/// The yearly schedule boiled down to its totals.
+#[derive(Clone, PartialEq, Debug, Deserialize, Serialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct ScheduleSummary {
+    pub periods: usize,
+    pub total_interest: i64,
+    pub closing_balance: i64,
+}
+
+impl ScheduleSummary {
+    pub fn new(schedule: &[ScheduleEntry]) -> Self {
+        Self {
+            periods: schedule.len(),
+            total_interest: schedule.iter().map(|entry| entry.interest).sum(),
+            closing_balance: schedule.last().map_or(0, |entry| entry.closing_balance),
+        }
+    }
+}
+
+impl InvestmentView {
+    pub fn new(investment: Investment, now: DateTime<Utc>, options: &CalcOptions) -> Self {
+        Self {
+            maturity_value: investment.maturity_value_with(options),
+            roi_percent: investment.roi_percent_with(options),
+            days_to_maturity: investment.days_to_maturity(now),
+            status: investment.status(now),
+            schedule_summary: None,
+            investment,
+        }
+    }
+
+    /// The list view plus a summary of the yearly schedule, for a detail page.
+    pub fn detailed(investment: Investment, now: DateTime<Utc>, options: &CalcOptions) -> Self {
+        let summary = ScheduleSummary::new(&investment.schedule_with(options));
+
+        Self {
+            schedule_summary: Some(summary),
+            ..Self::new(investment, now, options)
+        }
+    }
+}
+
+impl Investment {
+    /// When the lock-in ends; `None` without a lock-in or a start date.
+    pub fn lock_in_ends(&self) -> Option<DateTime<Utc>> {
+        let days = self.lock_in_days?;
+        Some(self.start_date? + chrono::Duration::days(i64::from(days)))
+    }
+
+    /// True when closing the investment at `at` falls inside its lock-in.
+    pub fn is_locked_in(&self, at: DateTime<Utc>) -> bool {
+        self.lock_in_ends().is_some_and(|ends| at < ends)
+    }
+
+    pub fn days_to_maturity(&self, now: DateTime<Utc>) -> Option<i64> {
+        self.end_date.map(|end| (end - now).num_days())
+    }
+
+    /// Share of the term elapsed by `now`, from 0.0 to 1.0. A zero-day term
+    /// jumps straight from 0 to 1 on its single day; without dates it is 0.
+    pub fn maturity_progress(&self, now: DateTime<Utc>) -> f64 {
+        let (Some(start), Some(end)) = (self.start_date, self.end_date) else {
+            return 0.0;
+        };
+        let term = (end - start).num_seconds();
+        if term <= 0 {
+            return if now >= end { 1.0 } else { 0.0 };
+        }
+
+        ((now - start).num_seconds() as f64 / term as f64).clamp(0.0, 1.0)
+    }
+
+    /// Closed or renewed when that was recorded, otherwise active or matured by date.
+    pub fn status(&self, now: DateTime<Utc>) -> Lifecycle {
+        match self
+            .inv_status
+            .as_ref()
+            .map(|status| status.status.as_str())
+        {
+            Some("closed") => return Lifecycle::Closed,
+            // before `renewed_to`, the successor of a renewal was the one marked renewed
+            Some("renewed") if self.renewed_to.is_some() => return Lifecycle::Renewed,
+            _ => {}
+        }
+        match self.end_date {
+            Some(end) if end <= now => Lifecycle::Matured,
+            _ => Lifecycle::Active,
+        }
+    }
+
+    /// Fills in `status` as of `now`, for a response.
+    pub fn fill_status(&mut self, now: DateTime<Utc>) {
+        self.status = Some(self.status(now));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+
+    fn date(year: i32, month: u32, day: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(year, month, day, 0, 0, 0).unwrap()
+    }
+
+    fn fd() -> Investment {
+        Investment {
+            inv_name: "SBI".into(),
+            inv_type: "FD".into(),
+            return_rate: 700,
+            return_type: "Ordinary".into(),
+            inv_amount: 100_000,
+            return_amount: 121_000,
+            start_date: Some(date(2021, 1, 1)),
+            end_date: Some(date(2024, 1, 1)),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn a_view_fills_in_the_derived_figures() {
+        let view = InvestmentView::new(fd(), date(2023, 12, 22), &CalcOptions::default());
+
+        assert_eq!(view.maturity_value, 121_000);
+        assert_eq!(view.roi_percent, 21.0);
+        assert_eq!(view.days_to_maturity, Some(10));
+        assert_eq!(view.status, Lifecycle::Active);
+        assert_eq!(view.schedule_summary, None);
+        assert_eq!(view.investment, fd());
+    }
+
+    #[test]
+    fn a_matured_view_counts_days_since_maturity() {
+        let view = InvestmentView::new(fd(), date(2024, 1, 11), &CalcOptions::default());
+
+        assert_eq!(view.days_to_maturity, Some(-10));
+        assert_eq!(view.status, Lifecycle::Matured);
+    }
+
+    #[test]
+    fn a_detailed_view_sums_up_the_schedule() {
+        let cumulative = Investment {
+            return_type: "Culmulative".into(),
+            ..fd()
+        };
+
+        let view = InvestmentView::detailed(cumulative, date(2022, 1, 1), &CalcOptions::default());
+
+        assert_eq!(
+            view.schedule_summary,
+            Some(ScheduleSummary {
+                periods: 3,
+                total_interest: 22_504,
+                closing_balance: 122_504,
+            })
+        );
+        assert_eq!(view.maturity_value, 122_504);
+    }
+
+    #[test]
+    fn locked_in_until_the_lock_in_ends() {
+        let inv = Investment {
+            lock_in_days: Some(90),
+            ..fd()
+        };
+
+        assert_eq!(inv.lock_in_ends(), Some(date(2021, 4, 1)));
+        assert!(inv.is_locked_in(date(2021, 3, 31)));
+        assert!(!inv.is_locked_in(date(2021, 4, 1)));
+    }
+
+    #[test]
+    fn never_locked_in_without_a_lock_in_or_a_start() {
+        let undated = Investment {
+            lock_in_days: Some(90),
+            start_date: None,
+            ..fd()
+        };
+
+        assert!(!fd().is_locked_in(date(2021, 1, 2)));
+        assert!(!undated.is_locked_in(date(2021, 1, 2)));
+    }
+}
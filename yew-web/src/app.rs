@@ -1,30 +1,106 @@
 use std::rc::Rc;
 
 use surrealdb::sql::Thing;
-use yew::{function_component, html, use_effect_with_deps, use_reducer, Callback, Html};
+use web_sys::KeyboardEvent;
+use yew::{
+    function_component, html, use_effect_with_deps, use_reducer, use_state, Callback,
+    ContextProvider, Html, MouseEvent,
+};
 
-use crate::components::{inv_list::InvestmentList, switcher::DarkModeContent};
-use crate::{controllers::*, state::InvestmentState};
+use crate::components::{
+    inv_list::InvestmentList, switcher::DarkModeContent, theme::ThemeProvider,
+};
+use crate::platform;
+use crate::preferences::{Preferences, PreferencesContext};
+use crate::{
+    controllers::*,
+    state::{InvestmentAction, InvestmentState},
+};
 use types::Investment;
 
+/// How long a just-changed row stays highlighted; matches the `flash` animation.
+const HIGHLIGHT_MILLIS: i32 = 2000;
+
+/// Keys that open or close the create form from anywhere on the page.
+const CREATE_FORM_KEYS: [&str; 2] = ["n", "c"];
+
+/// Whether a key press opens or closes the create form: a shortcut key
+/// pressed on its own, and not while the user is typing in a field.
+fn toggles_create_form(key: &str, plain: bool, editing: bool) -> bool {
+    plain && !editing && CREATE_FORM_KEYS.contains(&key)
+}
+
 #[function_component(App)]
 pub fn app() -> Html {
     let investments = use_reducer(InvestmentState::default);
     let investment_controller = Rc::new(InvestmentController::new(investments.clone()));
+    let preferences = use_state(Preferences::load);
+    let query = use_state(String::new);
+    // bumped by each shortcut press; the header toggles whenever it changes
+    let create_form_toggles = use_state(|| 0u32);
+    let preferences_context = PreferencesContext {
+        preferences: (*preferences).clone(),
+        update: {
+            let preferences = preferences.clone();
+            Callback::from(move |next: Preferences| {
+                next.save();
+                preferences.set(next);
+            })
+        },
+    };
 
-    // Get all investments on app startup
+    // Get all investments on app startup, and drop them when the app goes away (e.g. logout)
     {
         let investment_controller = investment_controller.clone();
+        let page_size = preferences.page_size;
 
         use_effect_with_deps(
             move |_| {
-                investment_controller.init_investments();
-                || {} // return empty destructor closure (cleanup use_effect)
+                investment_controller.init_investments(page_size);
+                move || investment_controller.clear_investments()
             },
             (),
         ); // only call on first render
     }
 
+    // re-installed after each press so the listener always bumps the current count,
+    // and removed when the app goes away
+    {
+        let create_form_toggles = create_form_toggles.clone();
+
+        use_effect_with_deps(
+            move |toggles| {
+                let toggles = *toggles;
+                let listener = platform::on_key_down(move |e: KeyboardEvent| {
+                    let plain = !(e.ctrl_key() || e.meta_key() || e.alt_key() || e.repeat());
+                    if toggles_create_form(&e.key(), plain, platform::is_editing()) {
+                        e.prevent_default();
+                        create_form_toggles.set(toggles.wrapping_add(1));
+                    }
+                });
+                move || drop(listener)
+            },
+            *create_form_toggles,
+        );
+    }
+
+    // each change gets its own timer; clearing is ignored once a newer change has taken over
+    {
+        let investments = investments.clone();
+
+        use_effect_with_deps(
+            move |generation| {
+                let generation = *generation;
+                if investments.highlight.id.is_some() {
+                    platform::set_timeout(HIGHLIGHT_MILLIS, move || {
+                        investments.dispatch(InvestmentAction::ClearHighlight(generation))
+                    });
+                }
+            },
+            investments.highlight.generation,
+        );
+    }
+
     let on_create_investment = {
         let investment_controller = investment_controller.clone();
 
@@ -37,12 +113,71 @@ pub fn app() -> Html {
         Callback::from(move |id: Thing| investment_controller.delete_investment(id))
     };
 
+    let on_archive_investment = {
+        let investment_controller = investment_controller.clone();
+
+        Callback::from(move |id: Thing| investment_controller.archive_investment(id))
+    };
+
+    let on_delete_investments = {
+        let investment_controller = investment_controller.clone();
+
+        Callback::from(move |ids: Vec<Thing>| investment_controller.delete_investments(ids))
+    };
+
+    let on_archive_investments = {
+        let investment_controller = investment_controller.clone();
+
+        Callback::from(move |ids: Vec<Thing>| investment_controller.archive_investments(ids))
+    };
+
+    let on_move_investment = {
+        let investment_controller = investment_controller.clone();
+
+        Callback::from(move |(dragged, target): (String, Thing)| {
+            investment_controller.move_investment(dragged, target)
+        })
+    };
+
     let on_edit_investment = {
         let investment_controller = investment_controller.clone();
 
         Callback::from(move |inv: Investment| investment_controller.edit_investment(inv))
     };
 
+    let on_change_page = {
+        let investment_controller = investment_controller.clone();
+        let preferences_context = preferences_context.clone();
+
+        Callback::from(move |(limit, offset): (usize, usize)| {
+            if preferences_context.preferences.page_size != Some(limit) {
+                preferences_context.update.emit(Preferences {
+                    page_size: Some(limit),
+                    ..preferences_context.preferences.clone()
+                });
+            }
+            investment_controller.load_page(Some(limit), offset)
+        })
+    };
+
+    // starts over from the server's copy, search included
+    let on_reset = {
+        let investment_controller = investment_controller.clone();
+        let page_size = preferences.page_size;
+        let query = query.clone();
+
+        Callback::from(move |_: MouseEvent| {
+            query.set(String::new());
+            investment_controller.reset_investments(page_size)
+        })
+    };
+
+    let on_search = {
+        let query = query.clone();
+
+        Callback::from(move |text: String| query.set(text))
+    };
+
     let on_renew_investment = {
         let investment_controller = investment_controller.clone();
 
@@ -52,31 +187,36 @@ pub fn app() -> Html {
     };
 
     html! {
-        <div class="flex flex-col mt-14 mx-auto gap-6">
-            <header class="flex flex-col mx-auto w-full text-text-50">
-            </header>
-            <main>
-                <div class="relative mx-auto my-4 w-full">
-                    <div class="absolute top-0 right-1/8 w-[30vw] h-[30vw] bg-accent-600 mix-blend-hard-light rounded-full filter blur-xl md:blur-2xl lg:blur-3xl opacity-70 animate-blob animation-delay-4000 pointer-events-none"></div>
-                    <div class="absolute top-0 left-1/4 w-[30vw] h-[30vw] bg-accent-600 mix-blend-hard-light rounded-full filter blur-xl md:blur-2xl lg:blur-3xl opacity-70 animate-blob animation-delay-2000 pointer-events-none"></div>
-                    <div class="absolute top-0 left-1/4 w-[40vw] h-[40vw] bg-secondary-600 mix-blend-hard-light rounded-full filter blur-xl md:blur-2xl lg:blur-3xl opacity-70 animate-blob pointer-events-none"></div>
-                    <div class="absolute top-3 left-1/2 w-[30vw] h-[30vw] bg-primary-600 mix-blend-hard-light rounded-full filter blur-xl md:blur-2xl lg:blur-3xl opacity-70 animate-blob animation-delay-3000 pointer-events-none"></div>
-                    <div class="absolute top-3 right-0 w-[30vw] h-[30vw] bg-accent-600 mix-blend-hard-light rounded-full filter blur-xl md:blur-2xl lg:blur-3xl opacity-70 animate-blob2 pointer-events-none"></div>
-                    <div class="flex">
-                        <h1 class="text-3xl font-black text-text-950">{"Investments"}</h1>
-                        <div class="ml-auto flex items-center">
-                            <DarkModeContent />
+        <ContextProvider<PreferencesContext> context={preferences_context}>
+        <ThemeProvider>
+            <div class="flex flex-col mt-14 mx-auto gap-6">
+                <header class="flex flex-col mx-auto w-full text-text-50">
+                </header>
+                <main>
+                    <div class="relative mx-auto my-4 w-full">
+                        <div class="absolute top-0 right-1/8 w-[30vw] h-[30vw] bg-accent-600 mix-blend-hard-light rounded-full filter blur-xl md:blur-2xl lg:blur-3xl opacity-70 animate-blob animation-delay-4000 pointer-events-none"></div>
+                        <div class="absolute top-0 left-1/4 w-[30vw] h-[30vw] bg-accent-600 mix-blend-hard-light rounded-full filter blur-xl md:blur-2xl lg:blur-3xl opacity-70 animate-blob animation-delay-2000 pointer-events-none"></div>
+                        <div class="absolute top-0 left-1/4 w-[40vw] h-[40vw] bg-secondary-600 mix-blend-hard-light rounded-full filter blur-xl md:blur-2xl lg:blur-3xl opacity-70 animate-blob pointer-events-none"></div>
+                        <div class="absolute top-3 left-1/2 w-[30vw] h-[30vw] bg-primary-600 mix-blend-hard-light rounded-full filter blur-xl md:blur-2xl lg:blur-3xl opacity-70 animate-blob animation-delay-3000 pointer-events-none"></div>
+                        <div class="absolute top-3 right-0 w-[30vw] h-[30vw] bg-accent-600 mix-blend-hard-light rounded-full filter blur-xl md:blur-2xl lg:blur-3xl opacity-70 animate-blob2 pointer-events-none"></div>
+                        <div class="flex">
+                            <h1 class="text-3xl font-black text-text-950">{"Investments"}</h1>
+                            <div class="ml-auto flex items-center gap-3">
+                                <button type="button" onclick={on_reset} title="Drop what is shown and load it again from the server" class="px-3 py-1.5 text-sm font-medium text-text-950 bg-background-50 hover:bg-background-100 rounded-lg ring-2 ring-primary-600 ring-inset focus:ring-4 focus:ring-primary-200">{"Reload"}</button>
+                                <DarkModeContent />
+                            </div>
+                        </div>
+                        <hr class="mb-6 border-t-2" />
+                        <div>
+                            <InvestmentList investments={investments.search(&query)} query={(*query).clone()} search={on_search} create_investment={on_create_investment} delete_investment={on_delete_investment} archive_investment={on_archive_investment} delete_investments={on_delete_investments} archive_investments={on_archive_investments} move_investment={on_move_investment} edit_investment={on_edit_investment} renew_investment={on_renew_investment} page={investments.page} change_page={on_change_page} highlighted={investments.highlight.id.clone()} create_form_toggles={*create_form_toggles}/>
                         </div>
                     </div>
-                    <hr class="mb-6 border-t-2" />
-                    <div>
-                        <InvestmentList investments={investments.investments.clone()} create_investment={on_create_investment} delete_investment={on_delete_investment} edit_investment={on_edit_investment} renew_investment={on_renew_investment}/>
-                    </div>
-                </div>
-            </main>
-            <footer class="mt-3 mb-6">
-            </footer>
-        </div>
+                </main>
+                <footer class="mt-3 mb-6">
+                </footer>
+            </div>
+        </ThemeProvider>
+        </ContextProvider<PreferencesContext>>
     }
 }
 
@@ -104,3 +244,21 @@ pub fn app() -> Html {
  * https://docs.rs/yew/0.20.0/yew/functional/fn.use_reducer.html
  * https://flowbite.com/docs/forms/checkbox/#bordered
  */
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn a_shortcut_key_toggles_the_create_form() {
+        assert!(toggles_create_form("n", true, false));
+        assert!(toggles_create_form("c", true, false));
+        assert!(!toggles_create_form("x", true, false));
+    }
+
+    #[test]
+    fn typing_in_a_field_or_a_modifier_is_not_a_shortcut() {
+        assert!(!toggles_create_form("n", true, true));
+        assert!(!toggles_create_form("n", false, false));
+    }
+}
@@ -1,13 +1,42 @@
 use std::collections::HashMap;
 
-use chrono::{DateTime, Utc};
-use yew::{html, Callback, Event, Html, InputEvent, Properties};
+use chrono::{DateTime, Months, Utc};
+use yew::{html, Callback, Event, Html, InputEvent, MouseEvent, NodeRef, Properties};
 
+use crate::format::{format_indian, parse_amount, parse_rate};
+use crate::messages::{MessageCatalog, ValidationMessage};
+use types::validation::{is_reasonable_date, return_amount_mismatch};
 use types::Investment;
 
-#[derive(Properties, PartialEq, Clone)]
+/// Longest amount that fits, as displayed: `92,23,37,20,36,85,47,75,807`.
+fn max_amount_length() -> usize {
+    format_indian(i64::MAX).len()
+}
+
+/// Help shown under the Investment Type field.
+pub const INV_TYPE_HELP: &str =
+    "FD: a lump sum deposited once. RD: a fixed amount deposited every month.";
+
+/// Help shown under the Return Type field.
+pub const RETURN_TYPE_HELP: &str =
+    "Ordinary pays the interest out periodically. Culmulative reinvests it and pays everything at maturity.";
+
+/// Help shown under the Return Rate field.
+pub const RETURN_RATE_HELP: &str =
+    "Annual interest rate in percent, up to two decimals, e.g. 7.25 for 7.25% p.a.";
+
+/// Terms offered as one-click end dates, in years after the start date.
+const QUICK_PICK_YEARS: [u32; 3] = [1, 3, 5];
+
+#[derive(Properties, PartialEq, Clone, Default)]
 pub struct BaseFormComponent {
     pub error_messages: HashMap<String, String>,
+    /// Shown like errors but never block submitting.
+    pub warnings: HashMap<String, String>,
+    /// Where validation messages get their text; English unless replaced.
+    pub messages: MessageCatalog,
+    /// Investor names already in use, offered as suggestions on the name field.
+    pub name_suggestions: Vec<String>,
 }
 
 impl BaseFormComponent {
@@ -17,19 +46,74 @@ impl BaseFormComponent {
         field_type: &str,
         field_value: &str,
         on_input: Callback<InputEvent>,
+        help_text: Option<&str>,
+        node_ref: NodeRef,
     ) -> Html {
         let field_id_string = field_id.to_string();
         html! {
             <div>
                 <label for={field_id_string.clone()} class="block mb-2 text-sm font-medium">{self.kebab_to_title(field_id)}</label>
                 <input
+                    ref={node_ref}
                     type={field_type.to_string()}
+                    step={(field_type == "number").then_some("0.01")}
+                    value={field_value.to_string()}
+                    oninput={on_input}
+                    id={field_id_string.clone()}
+                    class="border border-background-300 text-text-950 text-sm rounded-lg block w-full p-2.5 bg-background-50 placeholder-text-400"
+                />
+                { self.help(help_text) }
+                { self.error(field_id) }
+            </div>
+        }
+    }
+
+    /// The investor name input, with a datalist of names already in use.
+    pub fn name_field(&self, field_value: &str, on_input: Callback<InputEvent>) -> Html {
+        html! {
+            <div>
+                <label for="name" class="block mb-2 text-sm font-medium">{self.kebab_to_title("name")}</label>
+                <input
+                    type="text"
                     value={field_value.to_string()}
                     oninput={on_input}
+                    id="name"
+                    list="name-suggestions"
+                    autocomplete="off"
+                    class="border border-background-300 text-text-950 text-sm rounded-lg block w-full p-2.5 bg-background-50 placeholder-text-400"
+                />
+                <datalist id="name-suggestions">
+                    { for self.name_suggestions.iter().map(|name| html! { <option value={name.clone()} /> }) }
+                </datalist>
+                { self.error("name") }
+            </div>
+        }
+    }
+
+    /// A text input that shows the amount with Indian digit grouping while it is typed.
+    pub fn amount_field(
+        &self,
+        field_id: &str,
+        amount: i64,
+        on_input: Callback<InputEvent>,
+        help_text: Option<&str>,
+    ) -> Html {
+        let field_id_string = field_id.to_string();
+        html! {
+            <div>
+                <label for={field_id_string.clone()} class="block mb-2 text-sm font-medium">{self.kebab_to_title(field_id)}</label>
+                <input
+                    type="text"
+                    inputmode="numeric"
+                    maxlength={max_amount_length().to_string()}
+                    value={format_indian(amount)}
+                    oninput={on_input}
                     id={field_id_string.clone()}
                     class="border border-background-300 text-text-950 text-sm rounded-lg block w-full p-2.5 bg-background-50 placeholder-text-400"
                 />
+                { self.help(help_text) }
                 { self.error(field_id) }
+                { self.warning(field_id) }
             </div>
         }
     }
@@ -40,6 +124,7 @@ impl BaseFormComponent {
         field_value: &str,
         options: Html,
         on_change: Callback<Event>,
+        help_text: Option<&str>,
     ) -> Html {
         let field_id_string = field_id.to_string();
         html! {
@@ -54,6 +139,7 @@ impl BaseFormComponent {
                     <option selected={field_value.is_empty()} disabled=true value={""}>{""}</option>
                     { options }
                 </select>
+                { self.help(help_text) }
                 { self.error(field_id) }
             </div>
         }
@@ -64,22 +150,52 @@ impl BaseFormComponent {
         field_id: &str,
         field_value: &str,
         on_input: Callback<InputEvent>,
+        node_ref: NodeRef,
+        quick_picks: Html,
     ) -> Html {
         let field_id_string = field_id.to_string();
         html! {
             <div>
                 <label for={field_id_string.clone()} class="block mb-2 text-sm font-medium">{self.kebab_to_title(field_id)}</label>
                 <input
+                    ref={node_ref}
                     type="date"
                     value={field_value.to_string()}
                     oninput={on_input}
                     id={field_id_string.clone()}
                     class="border border-background-300 text-text-950 text-sm rounded-lg block w-full p-2.5 bg-background-50 placeholder-text-400 dark:input-dark"
                 />
+                { quick_picks }
                 { self.error(field_id) }
             </div>
         }
     }
+
+    /// Buttons that set the end date a whole number of years after `start`.
+    /// Nothing to pick from until there is a start date.
+    pub fn end_date_quick_picks(
+        &self,
+        start: Option<DateTime<Utc>>,
+        on_pick: Callback<DateTime<Utc>>,
+    ) -> Html {
+        let Some(start) = start else {
+            return html! {};
+        };
+
+        html! {
+            <div class="flex gap-2 mt-2">
+                { for QUICK_PICK_YEARS.into_iter().filter_map(|years| {
+                    let end = years_after(start, years)?;
+                    let onclick = on_pick.reform(move |_: MouseEvent| end);
+                    let label = if years == 1 { "+1 year".to_string() } else { format!("+{years} years") };
+                    Some(html! {
+                        <button type="button" {onclick} class="px-2 py-1 text-xs rounded bg-background-200 hover:bg-background-300">{label}</button>
+                    })
+                }) }
+            </div>
+        }
+    }
+
     pub fn update_field(&mut self, investment: &mut Investment, field: &str, value: String) {
         match field {
             "inv-name" => {
@@ -95,23 +211,28 @@ impl BaseFormComponent {
                 investment.return_type = value;
             }
             "return-rate" => {
-                investment.return_rate = value.parse().unwrap_or(0);
+                investment.return_rate = parse_rate(&value).unwrap_or(0);
             }
-            "inv-amount" => {
-                investment.inv_amount = value.parse().unwrap_or(0);
-                if investment.inv_amount < investment.return_amount {
-                    self.error_messages.remove("return-amount");
+            "inv-amount" | "return-amount" => {
+                // keep the last good amount rather than silently zeroing it
+                let Some(amount) = parse_amount(&value) else {
+                    self.report(field, ValidationMessage::AmountTooLarge);
+                    return;
+                };
+                if field == "inv-amount" {
+                    investment.inv_amount = amount;
+                } else {
+                    investment.return_amount = amount;
                 }
-            }
-            "return-amount" => {
-                investment.return_amount = value.parse().unwrap_or(0);
                 if investment.inv_amount < investment.return_amount {
                     self.error_messages.remove("inv-amount");
+                    self.error_messages.remove("return-amount");
                 }
             }
             _ => {}
         }
         self.error_messages.remove(field);
+        self.check_return_amount(investment);
     }
 
     pub fn update_date_field(
@@ -130,96 +251,86 @@ impl BaseFormComponent {
             _ => {}
         }
         self.error_messages.remove(field);
+        self.check_return_amount(investment);
     }
 
-    pub fn validate_form(&mut self, investment: &mut Investment) -> bool {
-        let mut is_valid = true;
-
-        if investment.inv_name.is_empty() {
-            self.error_messages.insert(
-                "inv-name".to_string(),
-                "Investment Name can not be blank".to_string(),
-            );
-            is_valid = false;
-        }
-
-        if investment.name.is_empty() {
-            self.error_messages
-                .insert("name".to_string(), "Name can not be blank".to_string());
-            is_valid = false;
-        }
-
-        if investment.inv_type.is_empty() {
-            self.error_messages.insert(
-                "inv-type".to_string(),
-                "Investment Type can not be blank".to_string(),
-            );
-            is_valid = false;
-        }
-
-        if investment.return_type.is_empty() {
-            self.error_messages.insert(
-                "return-type".to_string(),
-                "Return Type can not be blank".to_string(),
-            );
-            is_valid = false;
+    /// Warns, without blocking, when the return amount doesn't match the rate and dates.
+    pub fn check_return_amount(&mut self, investment: &Investment) {
+        match return_amount_mismatch(investment) {
+            Some(expected) => {
+                let message = self
+                    .messages
+                    .get(ValidationMessage::ReturnAmountMismatch(expected));
+                self.warnings.insert("return-amount".to_string(), message);
+            }
+            None => {
+                self.warnings.remove("return-amount");
+            }
         }
+    }
 
-        if investment.inv_amount == 0 {
-            self.error_messages.insert(
-                "inv-amount".to_string(),
-                "Investment Amount can not be blank".to_string(),
-            );
-            is_valid = false;
-        }
+    pub fn validate_form(&mut self, investment: &mut Investment) -> bool {
+        let mut is_valid = true;
 
-        if investment.return_amount == 0 {
-            self.error_messages.insert(
-                "return-amount".to_string(),
-                "Return Amount can not be blank".to_string(),
-            );
-            is_valid = false;
+        let blanks = [
+            ("inv-name", investment.inv_name.is_empty()),
+            ("name", investment.name.is_empty()),
+            ("inv-type", investment.inv_type.is_empty()),
+            ("return-type", investment.return_type.is_empty()),
+            ("inv-amount", investment.inv_amount == 0),
+            ("return-amount", investment.return_amount == 0),
+        ];
+        for (field, blank) in blanks {
+            if blank {
+                self.report(field, ValidationMessage::Blank(field));
+                is_valid = false;
+            }
         }
 
         if investment.inv_amount > investment.return_amount {
-            self.error_messages.insert(
-                "inv-amount".to_string(),
-                "Investment Amount can not be more than Return Amount".to_string(),
-            );
-            self.error_messages.insert(
-                "return-amount".to_string(),
-                "Return Amount can not be less than Investment Amount".to_string(),
-            );
+            self.report("inv-amount", ValidationMessage::InvAmountAboveReturn);
+            self.report("return-amount", ValidationMessage::ReturnAmountBelowInv);
             is_valid = false;
         }
 
         if investment.return_rate == 0 {
-            self.error_messages.insert(
-                "return-rate".to_string(),
-                "Return Rate can not be blank".to_string(),
-            );
+            self.report("return-rate", ValidationMessage::Blank("return-rate"));
             is_valid = false;
         }
 
-        if investment.start_date.is_none() {
-            self.error_messages.insert(
-                "start-date".to_string(),
-                "Start Date can not be blank".to_string(),
-            );
-            is_valid = false;
-        }
-
-        if investment.end_date.is_none() {
-            self.error_messages.insert(
-                "end-date".to_string(),
-                "End Date can not be blank".to_string(),
-            );
-            is_valid = false;
+        for (field, date) in [
+            ("start-date", investment.start_date),
+            ("end-date", investment.end_date),
+        ] {
+            match date {
+                None => {
+                    self.report(field, ValidationMessage::Blank(field));
+                    is_valid = false;
+                }
+                Some(date) if !is_reasonable_date(&date) => {
+                    self.report(field, ValidationMessage::YearOutOfRange(field));
+                    is_valid = false;
+                }
+                Some(_) => {}
+            }
         }
 
         is_valid
     }
 
+    /// Shows `message` under `field_id`, in the language of `self.messages`.
+    fn report(&mut self, field_id: &str, message: ValidationMessage) {
+        self.error_messages
+            .insert(field_id.to_string(), self.messages.get(message));
+    }
+
+    pub fn help(&self, help_text: Option<&str>) -> Html {
+        match help_text {
+            Some(help_text) => html! { <p class="mt-1 text-xs text-text-500">{help_text}</p> },
+            None => html! {},
+        }
+    }
+
     pub fn error(&self, field_id: &str) -> Html {
         html! {
             <>
@@ -234,6 +345,15 @@ impl BaseFormComponent {
         }
     }
 
+    pub fn warning(&self, field_id: &str) -> Html {
+        match self.warnings.get(field_id) {
+            Some(warning) => {
+                html! { <p class="mt-2 text-sm text-yellow-700 dark:text-yellow-500">{warning}</p> }
+            }
+            None => html! {},
+        }
+    }
+
     pub fn kebab_to_title(&self, s: &str) -> String {
         s.split('-')
             .map(|part| {
@@ -248,5 +368,52 @@ impl BaseFormComponent {
     }
 }
 
+/// `start` moved on by whole years. A 29 February start lands on 28 February
+/// in years that have no leap day, the last day of the same month.
+pub fn years_after(start: DateTime<Utc>, years: u32) -> Option<DateTime<Utc>> {
+    start.checked_add_months(Months::new(years.checked_mul(12)?))
+}
+
 // TODO: use this https://gist.github.com/intendednull/85de78d965e728c0a5b675c64adbf3f7
 // to make this more generic and add validation via inbuild methods
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+
+    fn date(year: i32, month: u32, day: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(year, month, day, 0, 0, 0).unwrap()
+    }
+
+    #[test]
+    fn a_leap_day_lands_on_the_last_day_of_february() {
+        assert_eq!(years_after(date(2024, 2, 29), 1), Some(date(2025, 2, 28)));
+        assert_eq!(years_after(date(2024, 2, 29), 4), Some(date(2028, 2, 29)));
+    }
+
+    #[test]
+    fn an_ordinary_date_keeps_its_day() {
+        assert_eq!(years_after(date(2023, 4, 1), 5), Some(date(2028, 4, 1)));
+    }
+
+    #[test]
+    fn the_amount_field_fits_the_largest_amount() {
+        assert_eq!(max_amount_length(), "92,23,37,20,36,85,47,75,807".len());
+    }
+
+    #[test]
+    fn an_amount_too_large_is_reported_and_the_last_one_kept() {
+        let mut form = BaseFormComponent::default();
+        let mut investment = Investment {
+            inv_amount: 100_000,
+            ..Default::default()
+        };
+
+        form.update_field(&mut investment, "inv-amount", "12345678901234567890".into());
+
+        assert_eq!(investment.inv_amount, 100_000);
+        assert_eq!(form.error_messages["inv-amount"], "Amount too large");
+    }
+}
@@ -1,12 +1,14 @@
-use std::collections::HashMap;
-
 use chrono::{DateTime, NaiveDate, TimeZone, Utc};
 use web_sys::wasm_bindgen::JsCast;
 use web_sys::HtmlSelectElement;
 use yew::events::{Event, InputEvent};
-use yew::{html, Callback, Component, Html, Properties, SubmitEvent};
+use yew::{html, Callback, Component, Html, NodeRef, Properties, SubmitEvent};
 
-use super::base_inv_form::BaseFormComponent;
+use super::base_inv_form::{BaseFormComponent, INV_TYPE_HELP, RETURN_RATE_HELP, RETURN_TYPE_HELP};
+use crate::format::format_rate;
+use crate::inv_api::{check_duplicate, fetch_investor_names};
+use crate::platform;
+use crate::unload_guard::UnloadGuard;
 use types::Investment;
 
 #[derive(Properties, PartialEq, Clone)]
@@ -14,18 +16,34 @@ pub struct CreateInvForm {
     state: Investment,
     props: CreateInvFormProps,
     base: BaseFormComponent,
+    unload_guard: UnloadGuard,
+    /// A test harness can check focus by comparing `document.activeElement` with this ref's node.
+    first_field: NodeRef,
+    focus_first_field: bool,
+    /// Existing investments this one may re-enter; non-empty while asking whether to create anyway.
+    duplicates: Vec<Investment>,
 }
 
 #[derive(Properties, PartialEq, Clone)]
 pub struct CreateInvFormProps {
     pub create_investment: Callback<Investment>,
+    /// Whether the surrounding panel is expanded; opening it focuses the first field.
+    #[prop_or_default]
+    pub open: bool,
+    /// An existing investment to copy the type, rate and names from; dates and amounts stay blank.
+    #[prop_or_default]
+    pub template: Option<Investment>,
 }
 
 pub enum Form {
     Update(String, String),
     UpdateDate(String, Option<DateTime<Utc>>),
+    NameSuggestions(Vec<String>),
     Reset,
     Save,
+    DuplicatesChecked(Vec<Investment>),
+    ConfirmCreate,
+    CancelCreate,
 }
 
 impl Component for CreateInvForm {
@@ -33,65 +51,120 @@ impl Component for CreateInvForm {
     type Properties = CreateInvFormProps;
 
     fn create(ctx: &yew::Context<Self>) -> Self {
+        // suggestions are a convenience, so a failed fetch just leaves them empty
+        ctx.link().send_future(async {
+            Form::NameSuggestions(fetch_investor_names().await.unwrap_or_default())
+        });
+
+        let mut state = Investment::default();
+        if let Some(template) = &ctx.props().template {
+            prefill(&mut state, template);
+        }
+
         Self {
-            state: Investment {
-                id: None,
-                inv_name: "".to_string(),
-                name: "".to_string(),
-                inv_type: "".to_string(),
-                return_type: "".to_string(),
-                inv_amount: 0,
-                return_amount: 0,
-                return_rate: 0,
-                inv_status: None,
-                start_date: None,
-                end_date: None,
-                created_at: None,
-                updated_at: None,
-            },
+            state,
             props: CreateInvFormProps {
                 create_investment: ctx.props().create_investment.clone(),
+                open: ctx.props().open,
+                template: ctx.props().template.clone(),
             },
-            base: BaseFormComponent {
-                error_messages: HashMap::new(),
-            },
+            base: BaseFormComponent::default(),
+            unload_guard: UnloadGuard::default(),
+            first_field: NodeRef::default(),
+            focus_first_field: ctx.props().open,
+            duplicates: Vec::new(),
         }
     }
 
-    fn update(&mut self, _ctx: &yew::Context<Self>, msg: Self::Message) -> bool {
+    fn changed(&mut self, ctx: &yew::Context<Self>, _old_props: &Self::Properties) -> bool {
+        if ctx.props().open && !self.props.open {
+            self.focus_first_field = true;
+        }
+        self.props.open = ctx.props().open;
+        if ctx.props().template != self.props.template {
+            if let Some(template) = &ctx.props().template {
+                prefill(&mut self.state, template);
+            }
+            self.props.template = ctx.props().template.clone();
+        }
+        true
+    }
+
+    fn rendered(&mut self, _ctx: &yew::Context<Self>, _first_render: bool) {
+        if self.focus_first_field {
+            self.focus_first_field = false;
+            platform::focus_unless_editing(&self.first_field);
+        }
+    }
+
+    fn update(&mut self, ctx: &yew::Context<Self>, msg: Self::Message) -> bool {
         match msg {
             Form::Update(field, value) => {
                 self.base.update_field(&mut self.state, &field, value);
+                self.unload_guard.set_dirty(true);
             }
             Form::UpdateDate(field, date) => {
                 self.base.update_date_field(&mut self.state, &field, date);
+                self.unload_guard.set_dirty(true);
+            }
+            Form::NameSuggestions(names) => {
+                self.base.name_suggestions = names;
             }
             Form::Reset => {
                 self.reset_form();
             }
             Form::Save => {
-                if self.save_form() {
-                    self.reset_form();
+                if self.base.validate_form(&mut self.state) {
+                    let name = self.state.name.clone();
+                    let amount = self.state.inv_amount;
+                    let start = self
+                        .state
+                        .start_date
+                        .map(|d| d.format("%Y-%m-%d").to_string())
+                        .unwrap_or_default();
+                    // the check only warns, so when it fails the investment is created as usual
+                    ctx.link().send_future(async move {
+                        Form::DuplicatesChecked(
+                            check_duplicate(&name, amount, &start)
+                                .await
+                                .unwrap_or_default(),
+                        )
+                    });
+                }
+            }
+            Form::DuplicatesChecked(duplicates) => {
+                if duplicates.is_empty() {
+                    self.save_form();
+                } else {
+                    self.duplicates = duplicates;
                 }
             }
+            Form::ConfirmCreate => {
+                self.duplicates.clear();
+                self.save_form();
+            }
+            Form::CancelCreate => {
+                // back to the form with everything still filled in
+                self.duplicates.clear();
+            }
         }
         true
     }
     fn view(&self, ctx: &yew::Context<Self>) -> Html {
         html! {
+            <div class="mx-auto w-full relative">
             <form onsubmit={ctx.link().callback(|e: SubmitEvent| { e.prevent_default(); Form::Save })} class="mx-auto w-full">
                 <div class="grid gap-6 mb-6 md:grid-cols-2 lg:grid-cols-3 text-text-950">
-                    { self.date_field(ctx, "start-date", &self.state.start_date.map(|d| d.format("%Y-%m-%d").to_string()).unwrap_or_default()) }
-                    { self.date_field(ctx, "end-date", &self.state.end_date.map(|d| d.format("%Y-%m-%d").to_string()).unwrap_or_default()) }
-                    { self.input_field(ctx, "inv-name", "text", &self.state.inv_name) }
-                    { self.input_field(ctx, "name", "text", &self.state.name) }
+                    { self.input_field(ctx, "inv-name", "text", &self.state.inv_name, None) }
+                    { self.name_field(ctx, &self.state.name) }
                     { self.select_field(ctx, "inv-type", &self.state.inv_type,
                         html! {
                             <>
                                 <option value="FD">{"FD"}</option>
                                 <option value="RD">{"RD"}</option>
                             </>
-                        }
+                        },
+                        Some(INV_TYPE_HELP)
                     ) }
                     { self.select_field(ctx, "return-type", &self.state.return_type,
                         html! {
@@ -99,15 +172,37 @@ impl Component for CreateInvForm {
                                 <option value="Ordinary">{"Ordinary"}</option>
                                 <option value="Culmulative">{"Culmulative"}</option>
                             </>
-                        }
+                        },
+                        Some(RETURN_TYPE_HELP)
                     ) }
-                    { self.input_field(ctx, "return-amount", "number", &self.state.return_amount.to_string()) }
-                    { self.input_field(ctx, "inv-amount", "number", &self.state.inv_amount.to_string()) }
-                    { self.input_field(ctx, "return-rate", "number", &self.state.return_rate.to_string()) }
+                    { self.amount_field(ctx, "inv-amount", self.state.inv_amount, None) }
+                    { self.amount_field(ctx, "return-amount", self.state.return_amount, None) }
+                    { self.input_field(ctx, "return-rate", "number", &format_rate(self.state.return_rate), Some(RETURN_RATE_HELP)) }
+                    { self.date_field(ctx, "start-date", &self.state.start_date.map(|d| d.format("%Y-%m-%d").to_string()).unwrap_or_default()) }
+                    { self.date_field(ctx, "end-date", &self.state.end_date.map(|d| d.format("%Y-%m-%d").to_string()).unwrap_or_default()) }
                     <button type="button" onclick={ctx.link().callback(|_| Form::Reset)} class="inline-flex justify-center items-center px-5 py-2.5 mt-3 sm:mt-5 text-sm font-medium text-center text-text-950 bg-background-50 hover:bg-background-100 rounded-lg ring-2 ring-primary-600 ring-inset focus:ring-4 focus:ring-primary-200">{"Reset"}</button>
                     <button type="submit" class="inline-flex justify-center items-center px-5 py-2.5 mt-3 sm:mt-5 text-sm font-medium text-center text-text-50 bg-primary-600 rounded-lg focus:ring-4 focus:ring-primary-200 hover:bg-primary-700">{"Save"}</button>
                 </div>
             </form>
+            {if self.duplicates.is_empty() { html! {} } else {
+                html! {
+                    <div class="absolute inset-0 flex items-center justify-center bg-white dark:bg-black bg-opacity-80 dark:bg-opacity-70">
+                        <div class="bg-background-50 p-4 rounded text-text-950">
+                            <p class="mb-2">{"Possible duplicate — create anyway?"}</p>
+                            <ul class="mb-2 text-sm">
+                                { for self.duplicates.iter().map(|inv| html! {
+                                    <li>{ format!("{} · {} · {}", inv.inv_name, inv.name, inv.inv_amount) }</li>
+                                }) }
+                            </ul>
+                            <div class="flex justify-center">
+                                <button type="button" onclick={ctx.link().callback(|_| Form::ConfirmCreate)} class="bg-red-500 px-4 py-2 mr-1 rounded">{"Create anyway"}</button>
+                                <button type="button" onclick={ctx.link().callback(|_| Form::CancelCreate)} class="bg-background-500 px-4 py-2 ml-1 rounded">{"Cancel"}</button>
+                            </div>
+                        </div>
+                    </div>
+                }
+            }}
+            </div>
         }
     }
 }
@@ -119,6 +214,44 @@ impl CreateInvForm {
         field_id: &str,
         field_type: &str,
         field_value: &str,
+        help_text: Option<&str>,
+    ) -> Html {
+        let field_id_str = field_id.to_string();
+        let on_input = ctx.link().callback(move |e: InputEvent| {
+            let input: web_sys::HtmlInputElement = e.target().unwrap().dyn_into().unwrap();
+            Form::Update(field_id_str.clone(), input.value())
+        });
+
+        // the first field on the form is the one focused when it opens
+        let node_ref = if field_id == "inv-name" {
+            self.first_field.clone()
+        } else {
+            NodeRef::default()
+        };
+        self.base.input_field(
+            field_id,
+            field_type,
+            field_value,
+            on_input,
+            help_text,
+            node_ref,
+        )
+    }
+
+    fn name_field(&self, ctx: &yew::Context<Self>, field_value: &str) -> Html {
+        let on_input = ctx.link().callback(move |e: InputEvent| {
+            let input: web_sys::HtmlInputElement = e.target().unwrap().dyn_into().unwrap();
+            Form::Update("name".to_string(), input.value())
+        });
+        self.base.name_field(field_value, on_input)
+    }
+
+    fn amount_field(
+        &self,
+        ctx: &yew::Context<Self>,
+        field_id: &str,
+        amount: i64,
+        help_text: Option<&str>,
     ) -> Html {
         let field_id_str = field_id.to_string();
         let on_input = ctx.link().callback(move |e: InputEvent| {
@@ -126,7 +259,7 @@ impl CreateInvForm {
             Form::Update(field_id_str.clone(), input.value())
         });
         self.base
-            .input_field(field_id, field_type, field_value, on_input)
+            .amount_field(field_id, amount, on_input, help_text)
     }
 
     fn select_field(
@@ -135,6 +268,7 @@ impl CreateInvForm {
         field_id: &str,
         field_value: &str,
         options: Html,
+        help_text: Option<&str>,
     ) -> Html {
         let field_id_str = field_id.to_string();
         let on_change = ctx.link().callback(move |e: Event| {
@@ -144,7 +278,7 @@ impl CreateInvForm {
             Form::Update(field_id_str.clone(), value)
         });
         self.base
-            .select_field(field_id, field_value, options, on_change)
+            .select_field(field_id, field_value, options, on_change, help_text)
     }
 
     fn date_field(&self, ctx: &yew::Context<Self>, field_id: &str, field_value: &str) -> Html {
@@ -161,20 +295,28 @@ impl CreateInvForm {
             Form::UpdateDate(field_id_str.clone(), date)
         });
 
-        self.base.date_field(field_id, field_value, on_input)
+        let quick_picks = if field_id == "end-date" {
+            let on_pick = ctx
+                .link()
+                .callback(|end| Form::UpdateDate("end-date".to_string(), Some(end)));
+            self.base
+                .end_date_quick_picks(self.state.start_date, on_pick)
+        } else {
+            html! {}
+        };
+        self.base.date_field(
+            field_id,
+            field_value,
+            on_input,
+            NodeRef::default(),
+            quick_picks,
+        )
     }
 
-    fn save_form(&mut self) -> bool {
-        // Validate form fields
-        let is_valid = self.base.validate_form(&mut self.state);
-
-        if is_valid {
-            self.props.create_investment.emit(self.state.clone());
-            true
-        } else {
-            // If the form is not valid, return false
-            false
-        }
+    /// Sends the already validated investment off and clears the form for the next one.
+    fn save_form(&mut self) {
+        self.props.create_investment.emit(self.state.clone());
+        self.reset_form();
     }
 
     fn reset_form(&mut self) {
@@ -187,5 +329,15 @@ impl CreateInvForm {
         self.state.return_rate = 0;
         self.state.start_date = None;
         self.state.end_date = None;
+        self.unload_guard.set_dirty(false);
     }
 }
+
+/// Copies what a new investment usually shares with `template`: its names, type and rate.
+fn prefill(state: &mut Investment, template: &Investment) {
+    state.inv_name = template.inv_name.clone();
+    state.name = template.name.clone();
+    state.inv_type = template.inv_type.clone();
+    state.return_type = template.return_type.clone();
+    state.return_rate = template.return_rate;
+}
@@ -1,12 +1,13 @@
-use std::collections::HashMap;
-
 use chrono::{DateTime, NaiveDate, TimeZone, Utc};
 use web_sys::wasm_bindgen::JsCast;
 use web_sys::{HtmlSelectElement, MouseEvent};
 use yew::events::{Event, InputEvent};
-use yew::{html, Callback, Component, Html, Properties};
+use yew::{html, Callback, Component, Html, NodeRef, Properties};
 
-use super::base_inv_form::BaseFormComponent;
+use super::base_inv_form::{BaseFormComponent, INV_TYPE_HELP, RETURN_RATE_HELP, RETURN_TYPE_HELP};
+use crate::format::format_rate;
+use crate::inv_api::fetch_investor_names;
+use crate::unload_guard::UnloadGuard;
 use types::Investment;
 
 #[derive(Properties, PartialEq, Clone)]
@@ -15,6 +16,7 @@ pub struct EditInvForm {
     show_edit_confirmation: bool,
     props: EditInvFormProps,
     base: BaseFormComponent,
+    unload_guard: UnloadGuard,
 }
 
 #[derive(Properties, PartialEq, Clone)]
@@ -27,9 +29,11 @@ pub struct EditInvFormProps {
 pub enum Form {
     Update(String, String),
     UpdateDate(String, Option<DateTime<Utc>>),
+    NameSuggestions(Vec<String>),
     Confirm,
     Cancel,
     Edit,
+    Reset,
 }
 
 impl Component for EditInvForm {
@@ -37,6 +41,11 @@ impl Component for EditInvForm {
     type Properties = EditInvFormProps;
 
     fn create(ctx: &yew::Context<Self>) -> Self {
+        // suggestions are a convenience, so a failed fetch just leaves them empty
+        ctx.link().send_future(async {
+            Form::NameSuggestions(fetch_investor_names().await.unwrap_or_default())
+        });
+
         Self {
             form_changed: false,
             show_edit_confirmation: false,
@@ -45,35 +54,52 @@ impl Component for EditInvForm {
                 investment: ctx.props().investment.clone(),
                 on_edit: ctx.props().on_edit.clone(),
             },
-            base: BaseFormComponent {
-                error_messages: HashMap::new(),
-            },
+            base: BaseFormComponent::default(),
+            unload_guard: UnloadGuard::default(),
         }
     }
 
-    fn update(&mut self, _ctx: &yew::Context<Self>, msg: Self::Message) -> bool {
+    fn update(&mut self, ctx: &yew::Context<Self>, msg: Self::Message) -> bool {
         match msg {
             Form::Update(field, value) => {
                 self.base
                     .update_field(&mut self.props.investment, &field, value);
                 self.form_changed = true;
+                self.unload_guard.set_dirty(true);
             }
             Form::UpdateDate(field, date) => {
                 self.base
                     .update_date_field(&mut self.props.investment, &field, date);
                 self.form_changed = true;
+                self.unload_guard.set_dirty(true);
+            }
+            Form::NameSuggestions(names) => {
+                self.base.name_suggestions = names;
             }
             Form::Confirm => {
+                self.show_edit_confirmation = false;
+                // only a save that went out clears the pending edits
                 if self.save_form() {
+                    self.form_changed = false;
+                    self.unload_guard.set_dirty(false);
                     self.props.on_edit.emit(());
                 }
             }
             Form::Cancel => {
+                // back to the form with the edits and form_changed untouched
                 self.show_edit_confirmation = false;
             }
             Form::Edit => {
                 self.show_edit_confirmation = true;
             }
+            Form::Reset => {
+                // back to the investment as it was when the form opened
+                self.props.investment = ctx.props().investment.clone();
+                self.base.error_messages.clear();
+                self.base.warnings.clear();
+                self.form_changed = false;
+                self.unload_guard.set_dirty(false);
+            }
         }
         true
     }
@@ -84,15 +110,16 @@ impl Component for EditInvForm {
                     <div class="grid gap-6 mb-6 md:grid-cols-2 lg:grid-cols-3 text-text-950">
                         { self.date_field(ctx, "start-date", &self.props.investment.start_date.map(|d| d.format("%Y-%m-%d").to_string()).unwrap_or_default()) }
                         { self.date_field(ctx, "end-date", &self.props.investment.end_date.map(|d| d.format("%Y-%m-%d").to_string()).unwrap_or_default()) }
-                        { self.input_field(ctx, "inv-name", "text", &self.props.investment.inv_name) }
-                        { self.input_field(ctx, "name", "text", &self.props.investment.name) }
+                        { self.input_field(ctx, "inv-name", "text", &self.props.investment.inv_name, None) }
+                        { self.name_field(ctx, &self.props.investment.name) }
                         { self.select_field(ctx, "inv-type", &self.props.investment.inv_type,
                             html! {
                                 <>
                                     <option value="FD" selected={self.props.investment.inv_type == "FD"}>{"FD"}</option>
                                     <option value="RD" selected={self.props.investment.inv_type == "RD"}>{"RD"}</option>
                                 </>
-                            }
+                            },
+                            Some(INV_TYPE_HELP)
                         ) }
                         { self.select_field(ctx, "return-type", &self.props.investment.return_type,
                             html! {
@@ -100,11 +127,13 @@ impl Component for EditInvForm {
                                     <option value="Ordinary" selected={self.props.investment.return_type == "Ordinary"}>{"Ordinary"}</option>
                                     <option value="Culmulative" selected={self.props.investment.return_type == "Culmulative"}>{"Culmulative"} </option>
                                 </>
-                            }
+                            },
+                            Some(RETURN_TYPE_HELP)
                         ) }
-                        { self.input_field(ctx, "return-amount", "number", &self.props.investment.return_amount.to_string()) }
-                        { self.input_field(ctx, "inv-amount", "number", &self.props.investment.inv_amount.to_string()) }
-                        { self.input_field(ctx, "return-rate", "number", &self.props.investment.return_rate.to_string()) }
+                        { self.amount_field(ctx, "return-amount", self.props.investment.return_amount, None) }
+                        { self.amount_field(ctx, "inv-amount", self.props.investment.inv_amount, None) }
+                        { self.input_field(ctx, "return-rate", "number", &format_rate(self.props.investment.return_rate), Some(RETURN_RATE_HELP)) }
+                        <button type="button" disabled={!self.form_changed} onclick={ctx.link().callback(|_| Form::Reset)} class="inline-flex justify-center items-center px-5 py-2.5 mt-3 sm:mt-5 text-sm font-medium text-center text-text-950 bg-background-50 hover:bg-background-100 rounded-lg ring-2 ring-primary-600 ring-inset focus:ring-4 focus:ring-primary-200">{"Reset"}</button>
                         <button type="submit" disabled={!self.form_changed}
                             onclick={ctx.link().callback(|e: MouseEvent| {
                                 // prevent the webpage from moving to top when the button is clicked
@@ -141,6 +170,37 @@ impl EditInvForm {
         field_id: &str,
         field_type: &str,
         field_value: &str,
+        help_text: Option<&str>,
+    ) -> Html {
+        let field_id_str = field_id.to_string();
+        let on_input = ctx.link().callback(move |e: InputEvent| {
+            let input: web_sys::HtmlInputElement = e.target().unwrap().dyn_into().unwrap();
+            Form::Update(field_id_str.clone(), input.value())
+        });
+        self.base.input_field(
+            field_id,
+            field_type,
+            field_value,
+            on_input,
+            help_text,
+            NodeRef::default(),
+        )
+    }
+
+    fn name_field(&self, ctx: &yew::Context<Self>, field_value: &str) -> Html {
+        let on_input = ctx.link().callback(move |e: InputEvent| {
+            let input: web_sys::HtmlInputElement = e.target().unwrap().dyn_into().unwrap();
+            Form::Update("name".to_string(), input.value())
+        });
+        self.base.name_field(field_value, on_input)
+    }
+
+    fn amount_field(
+        &self,
+        ctx: &yew::Context<Self>,
+        field_id: &str,
+        amount: i64,
+        help_text: Option<&str>,
     ) -> Html {
         let field_id_str = field_id.to_string();
         let on_input = ctx.link().callback(move |e: InputEvent| {
@@ -148,7 +208,7 @@ impl EditInvForm {
             Form::Update(field_id_str.clone(), input.value())
         });
         self.base
-            .input_field(field_id, field_type, field_value, on_input)
+            .amount_field(field_id, amount, on_input, help_text)
     }
 
     fn select_field(
@@ -157,6 +217,7 @@ impl EditInvForm {
         field_id: &str,
         field_value: &str,
         options: Html,
+        help_text: Option<&str>,
     ) -> Html {
         let field_id_str = field_id.to_string();
         let on_change = ctx.link().callback(move |e: Event| {
@@ -166,7 +227,7 @@ impl EditInvForm {
             Form::Update(field_id_str.clone(), value)
         });
         self.base
-            .select_field(field_id, field_value, options, on_change)
+            .select_field(field_id, field_value, options, on_change, help_text)
     }
 
     fn date_field(&self, ctx: &yew::Context<Self>, field_id: &str, field_value: &str) -> Html {
@@ -183,7 +244,22 @@ impl EditInvForm {
             Form::UpdateDate(field_id_str.clone(), date)
         });
 
-        self.base.date_field(field_id, field_value, on_input)
+        let quick_picks = if field_id == "end-date" {
+            let on_pick = ctx
+                .link()
+                .callback(|end| Form::UpdateDate("end-date".to_string(), Some(end)));
+            self.base
+                .end_date_quick_picks(self.props.investment.start_date, on_pick)
+        } else {
+            html! {}
+        };
+        self.base.date_field(
+            field_id,
+            field_value,
+            on_input,
+            NodeRef::default(),
+            quick_picks,
+        )
     }
 
     fn save_form(&mut self) -> bool {
@@ -1,22 +1,37 @@
-use yew::prelude::{html, Callback, Component, Html, Properties};
+use std::collections::VecDeque;
+
+use web_sys::wasm_bindgen::JsCast;
+use web_sys::HtmlSelectElement;
+use yew::prelude::{html, Callback, Component, Event, Html, Properties};
 
 use types::Investment;
 
 use super::create_inv_form::CreateInvForm;
+use crate::format::{amount_to_words, format_indian};
 
 #[derive(Properties, PartialEq, Clone)]
 pub struct ExpandableHeader {
     open: bool,
+    /// The investment picked to start the new one from.
+    template: Option<Investment>,
     pub props: ExpandableHeaderProps,
 }
 
 #[derive(Properties, PartialEq, Clone)]
 pub struct ExpandableHeaderProps {
     pub create_investment: Callback<Investment>,
+    pub total: i64,
+    /// Offered as templates for a new investment.
+    #[prop_or_default]
+    pub investments: VecDeque<Investment>,
+    /// Each change opens or closes the form, e.g. from a keyboard shortcut.
+    #[prop_or_default]
+    pub toggles: u32,
 }
 
 pub enum ExpandableHeaderState {
     Toggle,
+    PickTemplate(Option<Investment>),
 }
 
 impl Component for ExpandableHeader {
@@ -26,28 +41,63 @@ impl Component for ExpandableHeader {
     fn create(ctx: &yew::Context<Self>) -> Self {
         Self {
             open: false,
+            template: None,
             props: ExpandableHeaderProps {
                 create_investment: ctx.props().create_investment.clone(),
+                total: ctx.props().total,
+                investments: ctx.props().investments.clone(),
+                toggles: ctx.props().toggles,
             },
         }
     }
 
+    fn changed(&mut self, ctx: &yew::Context<Self>, _old_props: &Self::Properties) -> bool {
+        if ctx.props().toggles != self.props.toggles {
+            self.props.toggles = ctx.props().toggles;
+            self.open = !self.open;
+        }
+        true
+    }
+
     fn update(&mut self, _ctx: &yew::Context<Self>, msg: Self::Message) -> bool {
         match msg {
             ExpandableHeaderState::Toggle => {
                 self.open = !self.open;
                 true
             }
+            ExpandableHeaderState::PickTemplate(template) => {
+                self.template = template;
+                true
+            }
         }
     }
 
     fn view(&self, ctx: &yew::Context<Self>) -> Html {
+        // read the total from the context so it follows the list after the initial fetch
+        let total = ctx.props().total;
+        let investments = ctx.props().investments.clone();
+        let on_pick_template = ctx.link().callback(move |e: Event| {
+            let select: HtmlSelectElement = e.target().unwrap().dyn_into().unwrap();
+            let value = select.value();
+            let template = investments
+                .iter()
+                .find(|investment| {
+                    investment.id.as_ref().map(|id| id.to_string()).as_deref()
+                        == Some(value.as_str())
+                })
+                .cloned();
+            ExpandableHeaderState::PickTemplate(template)
+        });
+
         html! {
             <div class="w-full mx-auto">
                 <div class="bg-background-50 rounded">
                     <div class="">
                         <button  class="flex items-center justify-between w-full p-3 font-medium rtl:text-left" onclick={ctx.link().callback(|_| ExpandableHeaderState::Toggle)}>
-                            <span class="flex items-center text-text-950"> {"Total: 5,00,000"}</span>
+                            <span class="flex flex-col items-start text-text-950">
+                                {format!("Total: {}", format_indian(total))}
+                                <span class="text-xs font-normal text-text-500">{amount_to_words(total)}</span>
+                            </span>
                                 <svg class="w-7 text-text-950" fill="currentColor" viewBox="0 0 24 24">
                                 <path d="M2 18H12V20H2V18ZM2 11H22V13H2V11ZM2 4H22V6H2V4ZM18 \
                                 18V15H20V18H23V20H20V23H18V20H15V18H18Z" />
@@ -55,8 +105,21 @@ impl Component for ExpandableHeader {
                         </button>
                         <div class={if self.open { "max-h-[1500px] overflow-hidden transition-all duration-100 ease-in-out" } else { "max-h-0 overflow-hidden transition-all duration-100 ease-in-out" }}>
                             <p class="w-full p-4 text-text-950 text-base bg-background-50 rounded-b">
+                                <div class="mb-4 flex items-center gap-2 text-sm">
+                                    <label for="template" class="text-text-500">{"Start from"}</label>
+                                    <select id="template" onchange={on_pick_template} class="bg-background-50 border border-background-300 text-text-950 rounded-lg focus:ring-primary-500 focus:border-primary-500 p-1.5">
+                                        <option value="" selected={self.template.is_none()}>{"A blank form"}</option>
+                                        { for ctx.props().investments.iter().filter_map(|investment| {
+                                            let id = investment.id.as_ref()?.to_string();
+                                            let selected = self.template.as_ref().and_then(|template| template.id.as_ref()) == investment.id.as_ref();
+                                            Some(html! {
+                                                <option value={id} {selected}>{ format!("{} · {}", investment.inv_name, investment.name) }</option>
+                                            })
+                                        }) }
+                                    </select>
+                                </div>
                                 <div class="w-full md:w-auto flex flex-col md:flex-row space-y-2 md:space-y-0 items-stretch md:items-center justify-end md:space-x-3 flex-shrink-0">
-                                    <CreateInvForm create_investment={self.props.create_investment.clone()} />
+                                    <CreateInvForm create_investment={self.props.create_investment.clone()} open={self.open} template={self.template.clone()} />
                                 </div>
                             </p>
                         </div>
@@ -1,8 +1,13 @@
+use gloo_dialogs::alert;
 use surrealdb::sql::Thing;
-use yew::{html, Callback, Component, Html, Properties};
+use yew::{html, Callback, Component, DragEvent, Event, Html, Properties};
 
 use super::edit_inv_form::EditInvForm;
 use super::renew_inv_form::RenewInvForm;
+use super::status_badge::StatusBadge;
+use crate::format::{amount_to_words, format_indian, format_percent, share_summary};
+use crate::platform::copy_to_clipboard;
+use types::interest::CalcOptions;
 use types::Investment;
 
 #[derive(Properties, PartialEq, Clone)]
@@ -11,6 +16,7 @@ pub struct InvestmentItem {
     open_edit: bool,
     open_renew: bool,
     show_delete_confirmation: bool,
+    copied: bool,
     props: InvestmentItemProps,
 }
 
@@ -19,8 +25,23 @@ pub struct InvestmentItemProps {
     pub investment: Investment,
     pub create_investment: Callback<Investment>,
     pub delete_investment: Callback<Thing>,
+    pub archive_investment: Callback<Thing>,
+    /// Ticked for a bulk archive or delete.
+    #[prop_or_default]
+    pub selected: bool,
+    /// Ticks or unticks the investment with this id.
+    #[prop_or_default]
+    pub toggle_selected: Callback<Thing>,
+    /// (id of the dragged investment, investment it was dropped on)
+    pub move_investment: Callback<(String, Thing)>,
     pub edit_investment: Callback<Investment>,
     pub renew_investment: Callback<(Investment, Investment)>,
+    /// Show the return net of tax at this rate; gross when unset.
+    #[prop_or_default]
+    pub tax_rate_percent: Option<f64>,
+    /// Flash the row because it was just added or edited.
+    #[prop_or_default]
+    pub highlighted: bool,
 }
 
 pub enum InvestmentItemState {
@@ -30,6 +51,11 @@ pub enum InvestmentItemState {
     ToggleDeleteConfirmation,
     ConfirmDelete,
     CancelDelete,
+    Archive,
+    Drop(String),
+    CopySummary,
+    /// Whether the clipboard accepted the summary.
+    SummaryCopied(bool),
 }
 
 impl Component for InvestmentItem {
@@ -42,17 +68,24 @@ impl Component for InvestmentItem {
             open_edit: false,
             open_renew: false,
             show_delete_confirmation: false,
+            copied: false,
             props: InvestmentItemProps {
                 investment: ctx.props().investment.clone(),
                 create_investment: ctx.props().create_investment.clone(),
                 delete_investment: ctx.props().delete_investment.clone(),
+                archive_investment: ctx.props().archive_investment.clone(),
+                selected: ctx.props().selected,
+                toggle_selected: ctx.props().toggle_selected.clone(),
+                move_investment: ctx.props().move_investment.clone(),
                 edit_investment: ctx.props().edit_investment.clone(),
                 renew_investment: ctx.props().renew_investment.clone(),
+                tax_rate_percent: ctx.props().tax_rate_percent,
+                highlighted: ctx.props().highlighted,
             },
         }
     }
 
-    fn update(&mut self, _ctx: &yew::Context<Self>, msg: Self::Message) -> bool {
+    fn update(&mut self, ctx: &yew::Context<Self>, msg: Self::Message) -> bool {
         match msg {
             InvestmentItemState::ToggleExpandMore => {
                 self.open_more = !self.open_more;
@@ -89,6 +122,35 @@ impl Component for InvestmentItem {
                 // Hide the confirmation overlay without deleting the item
                 self.show_delete_confirmation = false;
             }
+            InvestmentItemState::Archive => {
+                if let Some(id) = self.props.investment.id.clone() {
+                    self.props.archive_investment.emit(id);
+                }
+            }
+            InvestmentItemState::Drop(dragged) => {
+                if let Some(target) = self.props.investment.id.clone() {
+                    if !dragged.is_empty() && dragged != target.to_string() {
+                        self.props.move_investment.emit((dragged, target));
+                    }
+                }
+                return false;
+            }
+            InvestmentItemState::CopySummary => {
+                let summary = share_summary(&self.props.investment);
+                ctx.link().send_future(async move {
+                    InvestmentItemState::SummaryCopied(copy_to_clipboard(&summary).await.is_some())
+                });
+                return false;
+            }
+            InvestmentItemState::SummaryCopied(copied) => {
+                if !copied {
+                    alert(&format!(
+                        "Couldn't copy to the clipboard. Here is the summary to copy by hand:\n\n{}",
+                        share_summary(&self.props.investment)
+                    ));
+                }
+                self.copied = copied;
+            }
         }
         true
     }
@@ -117,6 +179,43 @@ impl Component for InvestmentItem {
             </svg>
         };
 
+        let return_amount = match ctx.props().tax_rate_percent {
+            Some(tax_rate_percent) => self.props.investment.net_return(tax_rate_percent),
+            None => self.props.investment.return_amount,
+        };
+
+        let drag_id = self
+            .props
+            .investment
+            .id
+            .as_ref()
+            .map(ToString::to_string)
+            .unwrap_or_default();
+        let on_drag_start = Callback::from(move |e: DragEvent| {
+            if let Some(data) = e.data_transfer() {
+                let _ = data.set_data("text/plain", &drag_id);
+            }
+        });
+        let on_toggle_selected = {
+            let id = self.props.investment.id.clone();
+            let toggle_selected = ctx.props().toggle_selected.clone();
+            Callback::from(move |_: Event| {
+                if let Some(id) = id.clone() {
+                    toggle_selected.emit(id);
+                }
+            })
+        };
+        // dropping is only allowed where dragover is cancelled
+        let on_drag_over = Callback::from(|e: DragEvent| e.prevent_default());
+        let on_drop = ctx.link().callback(|e: DragEvent| {
+            e.prevent_default();
+            let dragged = e
+                .data_transfer()
+                .and_then(|data| data.get_data("text/plain").ok())
+                .unwrap_or_default();
+            InvestmentItemState::Drop(dragged)
+        });
+
         // let date = investment
         //     .created_at
         //     .unwrap_or_default()
@@ -137,7 +236,13 @@ impl Component for InvestmentItem {
 
         html! {
                 <>
-                    <tr class={format!("{} {}", {if self.open_more || self.open_edit || self.open_renew { "bg-background-50" } else { "border-b dark:border-background-200 hover:bg-background-50" }}, "relative")}>
+                    // below the sm breakpoint the row is swapped for a stacked card
+                    { self.card_view(ctx, &end_date, return_amount, on_toggle_selected.clone()) }
+                    <tr ondragover={on_drag_over} ondrop={on_drop} class={format!("{} {} {}", {if self.open_more || self.open_edit || self.open_renew { "bg-background-50" } else { "border-b dark:border-background-200 hover:bg-background-50" }}, "relative hidden sm:table-row", if ctx.props().highlighted { "animate-flash" } else { "" })}>
+                        <td class="px-2 py-4">
+                            <input type="checkbox" checked={ctx.props().selected} onchange={on_toggle_selected} aria-label="Select" />
+                        </td>
+                        <td draggable="true" ondragstart={on_drag_start} title="Drag to reorder" class="px-2 py-4 cursor-move text-text-400 select-none">{"⋮⋮"}</td>
                         <td class="px-6 py-4 min-w-max whitespace-nowrap hidden sm:table-cell">
                             {start_date.clone()}
                             <dl class="lg:hidden">
@@ -164,14 +269,14 @@ impl Component for InvestmentItem {
                                 <dt class="sr-only">{"Return Type"}</dt>
                                 <dd class="mt-1">{&self.props.investment.clone().return_type}</dd>
                                 <dt class="sr-only">{"Return Rate"}</dt>
-                                <dd class="mt-1">{&self.props.investment.clone().return_rate}</dd>
+                                <dd class="mt-1">{format_percent(self.props.investment.return_rate)}</dd>
                             </dl>
                         </td>
                         <td class="px-6 py-4 min-w-max hidden lg:table-cell">{&self.props.investment.clone().return_type}</td>
-                        <td class="px-6 py-4 min-w-max hidden lg:table-cell">{&self.props.investment.clone().return_rate}</td>
-                        <td class="px-6 py-4 min-w-max hidden lg:table-cell">{&self.props.investment.clone().inv_amount} </td>
-                        <td class="px-6 py-4 min-w-max font-medium text-text-950">
-                            {&self.props.investment.clone().return_amount}
+                        <td class="px-6 py-4 min-w-max hidden lg:table-cell text-right tabular-nums">{format_percent(self.props.investment.return_rate)}</td>
+                        <td class="px-6 py-4 min-w-max hidden lg:table-cell text-right tabular-nums">{&self.props.investment.clone().inv_amount}</td>
+                        <td class="px-6 py-4 min-w-max font-medium text-text-950 text-right tabular-nums">
+                            {return_amount}
                             <dl class="lg:hidden font-normal text-text-500">
                                 <dt class="sr-only">{"Investment"}</dt>
                                 <dd class="mt-1">{&self.props.investment.clone().inv_amount}</dd>
@@ -198,6 +303,15 @@ impl Component for InvestmentItem {
                                     {"Renew"}{ if self.open_renew { arrow_up.clone() } else { arrow_down.clone() } }
                                 </div>
                             </button>
+                            <button onclick={ctx.link().callback(|_| InvestmentItemState::Archive)} class="font-medium text-text-600 hover:underline w-full">
+                                <div class="flex items-center justify-between w-full rtl:text-left">
+                                    {"Archive"}
+                                    <svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 16 16" fill="currentColor" class="w-4 h-4">
+                                        <path d="M2 3a1 1 0 0 1 1-1h10a1 1 0 0 1 1 1v1a1 1 0 0 1-1 1H3a1 1 0 0 1-1-1V3Z" />
+                                        <path fill-rule="evenodd" d="M3 6h10v6a2 2 0 0 1-2 2H5a2 2 0 0 1-2-2V6Zm3 2.75A.75.75 0 0 1 6.75 8h2.5a.75.75 0 0 1 0 1.5h-2.5A.75.75 0 0 1 6 8.75Z" clip-rule="evenodd" />
+                                    </svg>
+                                </div>
+                            </button>
                             <button onclick={ctx.link().callback(|_| InvestmentItemState::ToggleExpandMore)} class="w-full">
                                 <div class="flex items-center justify-between w-full rtl:text-left">
                                     {"More"}{ if self.open_more { arrow_up.clone() } else { arrow_down.clone() } }
@@ -231,9 +345,20 @@ impl Component for InvestmentItem {
                         html! {
                             <tr class="overflow-hidden border-b dark:border-background-200 hover:bg-background-50">
                                 <td colspan="100%">
-                                    <p class="p-4 text-text-950 text-base bg-background-50 rounded-b">
-                                        {self.props.investment.inv_status.as_ref().map_or("No status", |s| &s.status)}
-                                    </p>
+                                    <div class="p-4 text-text-950 text-base bg-background-50 rounded-b">
+                                        <StatusBadge status={self.props.investment.status(chrono::Utc::now())} />
+                                        <dl class="mt-2 text-sm text-text-500">
+                                            <dt class="inline">{"Investment: "}</dt>
+                                            <dd class="inline">{amount_to_words(self.props.investment.inv_amount)}</dd>
+                                            <br />
+                                            <dt class="inline">{"Return: "}</dt>
+                                            <dd class="inline">{amount_to_words(self.props.investment.return_amount)}</dd>
+                                        </dl>
+                                        { self.computed_details() }
+                                        <button onclick={ctx.link().callback(|_| InvestmentItemState::CopySummary)} class="mt-2 text-sm font-medium text-accent-600 hover:underline">
+                                            {if self.copied { "Copied!" } else { "Copy summary" }}
+                                        </button>
+                                    </div>
                                 </td>
                             </tr>
                         }
@@ -258,3 +383,118 @@ impl Component for InvestmentItem {
         }
     }
 }
+
+impl InvestmentItem {
+    /// Figures worked out from the rate and dates rather than typed in, with the year-by-year schedule.
+    fn computed_details(&self) -> Html {
+        let investment = &self.props.investment;
+        let options = CalcOptions::default();
+        let schedule = investment.schedule_with(&options);
+
+        html! {
+            <div class="mt-3 text-sm">
+                <dl class="grid grid-cols-2 sm:grid-cols-4 gap-2">
+                    <div>
+                        <dt class="text-text-500">{"Maturity value"}</dt>
+                        <dd class="font-medium">{format_indian(investment.maturity_value_with(&options))}</dd>
+                    </div>
+                    <div>
+                        <dt class="text-text-500">{"Interest"}</dt>
+                        <dd class="font-medium">{format_indian(investment.interest_earned_with(&options))}</dd>
+                    </div>
+                    <div>
+                        <dt class="text-text-500">{"ROI"}</dt>
+                        <dd class="font-medium">{format!("{}%", investment.roi_percent_with(&options))}</dd>
+                    </div>
+                    <div>
+                        <dt class="text-text-500">{"Annualized return"}</dt>
+                        <dd class="font-medium">{format!("{}%", investment.annualized_return())}</dd>
+                    </div>
+                </dl>
+                {if schedule.is_empty() {
+                    html! { <p class="mt-2 text-text-500">{"Add start and end dates to see the schedule."}</p> }
+                } else {
+                    html! {
+                        <table class="mt-2 w-full text-left text-text-600">
+                            <thead class="text-xs uppercase text-text-500">
+                                <tr>
+                                    <th class="py-1">{"Period ending"}</th>
+                                    <th class="py-1 text-right">{"Interest"}</th>
+                                    <th class="py-1 text-right">{"Balance"}</th>
+                                </tr>
+                            </thead>
+                            <tbody>
+                                { for schedule.iter().map(|entry| html! {
+                                    <tr>
+                                        <td class="py-1">{entry.period_end.date_naive().format("%d-%m-%Y").to_string()}</td>
+                                        <td class="py-1 text-right tabular-nums">{format_indian(entry.interest)}</td>
+                                        <td class="py-1 text-right tabular-nums">{format_indian(entry.closing_balance)}</td>
+                                    </tr>
+                                }) }
+                            </tbody>
+                        </table>
+                    }
+                }}
+            </div>
+        }
+    }
+
+    /// The investment as a card for narrow screens: name, amounts, maturity and actions.
+    fn card_view(
+        &self,
+        ctx: &yew::Context<Self>,
+        end_date: &str,
+        return_amount: i64,
+        on_toggle_selected: Callback<Event>,
+    ) -> Html {
+        let investment = &self.props.investment;
+
+        html! {
+            <tr class={format!("sm:hidden border-b dark:border-background-200 {}", if ctx.props().highlighted { "animate-flash" } else { "" })}>
+                <td colspan="100%" class="p-3">
+                    <div class="rounded-lg bg-background-50 p-3 text-text-950">
+                        <div class="flex items-start justify-between">
+                            <input type="checkbox" checked={ctx.props().selected} onchange={on_toggle_selected} aria-label="Select" class="mt-1 mr-2" />
+                            <div class="flex-1">
+                                <p class="font-medium">{&investment.inv_name}</p>
+                                <p class="text-xs text-text-500">{format!("{} · {} · {}", investment.name, investment.inv_type, investment.return_type)}</p>
+                            </div>
+                            <StatusBadge status={investment.status(chrono::Utc::now())} />
+                        </div>
+                        <dl class="mt-2 grid grid-cols-2 gap-1 text-sm">
+                            <dt class="text-text-500">{"Investment"}</dt>
+                            <dd class="text-right">{investment.inv_amount}</dd>
+                            <dt class="text-text-500">{"Return"}</dt>
+                            <dd class="text-right font-medium">{return_amount}</dd>
+                            <dt class="text-text-500">{"Rate"}</dt>
+                            <dd class="text-right">{format_percent(investment.return_rate)}</dd>
+                            <dt class="text-text-500">{"Matures"}</dt>
+                            <dd class="text-right">{end_date}</dd>
+                        </dl>
+                        {if self.show_delete_confirmation {
+                            html! {
+                                <div class="mt-3 flex items-center justify-between text-sm">
+                                    <span>{"Delete this Investment?"}</span>
+                                    <span>
+                                        <button onclick={ctx.link().callback(|_| InvestmentItemState::ConfirmDelete)} class="bg-red-500 px-3 py-1 mr-1 rounded">{"Confirm"}</button>
+                                        <button onclick={ctx.link().callback(|_| InvestmentItemState::CancelDelete)} class="bg-background-500 px-3 py-1 rounded">{"Cancel"}</button>
+                                    </span>
+                                </div>
+                            }
+                        } else {
+                            html! {
+                                <div class="mt-3 flex justify-between text-sm font-medium">
+                                    <button onclick={ctx.link().callback(|_| InvestmentItemState::ToggleExpandEdit)} class="text-accent-600 hover:underline">{"Edit"}</button>
+                                    <button onclick={ctx.link().callback(|_| InvestmentItemState::ToggleExpandRenew)} class="text-secondary-600 hover:underline">{"Renew"}</button>
+                                    <button onclick={ctx.link().callback(|_| InvestmentItemState::Archive)} class="text-text-600 hover:underline">{"Archive"}</button>
+                                    <button onclick={ctx.link().callback(|_| InvestmentItemState::ToggleExpandMore)} class="hover:underline">{"More"}</button>
+                                    <button onclick={ctx.link().callback(|_| InvestmentItemState::ToggleDeleteConfirmation)} class="text-red-600 dark:text-red-500 hover:underline">{"Delete"}</button>
+                                </div>
+                            }
+                        }}
+                    </div>
+                </td>
+            </tr>
+        }
+    }
+}
@@ -1,20 +1,56 @@
 use std::collections::VecDeque;
 
+use chrono::Utc;
+use gloo_dialogs::confirm;
 use surrealdb::sql::Thing;
 use uuid::Uuid;
-use yew::{function_component, html, Callback, Html, Properties};
+use web_sys::wasm_bindgen::JsCast;
+use web_sys::HtmlSelectElement;
+use yew::{
+    function_component, html, use_context, use_state, Callback, Event, Html, InputEvent,
+    MouseEvent, Properties, UseStateHandle,
+};
 
 use super::inv_item::InvestmentItem;
 use crate::components::exp_table_header::ExpandableHeader;
-use types::Investment;
+use crate::preferences::{Preferences, PreferencesContext};
+use crate::state::Page;
+use types::validation::is_reasonable_date;
+use types::{Investment, Lifecycle};
+
+/// Choices offered in the rows-per-page dropdown, besides the server's default.
+const PAGE_SIZES: [usize; 3] = [25, 50, 100];
+
+/// Tax slab assumed when returns are shown net of tax.
+const TAX_RATE_PERCENT: f64 = 30.0;
 
 #[derive(Properties, PartialEq)]
 pub struct InvestmentListProps {
     pub investments: VecDeque<Investment>,
     pub create_investment: Callback<Investment>,
     pub delete_investment: Callback<Thing>,
+    pub archive_investment: Callback<Thing>,
+    /// Deletes the selected investments with one request.
+    pub delete_investments: Callback<Vec<Thing>>,
+    /// Archives the selected investments with one request.
+    pub archive_investments: Callback<Vec<Thing>>,
+    pub move_investment: Callback<(String, Thing)>,
     pub edit_investment: Callback<Investment>,
     pub renew_investment: Callback<(Investment, Investment)>,
+    pub page: Page,
+    /// (rows per page, offset) to load instead.
+    pub change_page: Callback<(usize, usize)>,
+    /// The investment just added or edited.
+    #[prop_or_default]
+    pub highlighted: Option<Thing>,
+    /// What the list is filtered by; `investments` holds only the matches.
+    #[prop_or_default]
+    pub query: String,
+    #[prop_or_default]
+    pub search: Callback<String>,
+    /// Bumped by the keyboard shortcut that opens or closes the create form.
+    #[prop_or_default]
+    pub create_form_toggles: u32,
 }
 
 #[function_component(InvestmentList)]
@@ -23,12 +59,108 @@ pub fn investment_list(
         investments,
         create_investment,
         delete_investment,
+        archive_investment,
+        delete_investments,
+        archive_investments,
+        move_investment,
         edit_investment,
         renew_investment,
+        page,
+        change_page,
+        highlighted,
+        query,
+        search,
+        create_form_toggles,
     }: &InvestmentListProps,
 ) -> Html {
+    let total = investments
+        .iter()
+        .map(|investment| investment.inv_amount)
+        .sum::<i64>();
+
+    let context = use_context::<PreferencesContext>();
+    let preferences = context
+        .as_ref()
+        .map(|context| context.preferences.clone())
+        .unwrap_or_default();
+    let update_preferences = move |preferences: Preferences| {
+        if let Some(context) = &context {
+            context.update.emit(preferences);
+        }
+    };
+
+    // gross by default; one toggle flips every row
+    let show_net = preferences.show_net;
+    let tax_rate_percent = show_net.then_some(TAX_RATE_PERCENT);
+    let toggle_net = {
+        let preferences = preferences.clone();
+        let update_preferences = update_preferences.clone();
+        Callback::from(move |_: MouseEvent| {
+            update_preferences(Preferences {
+                show_net: !show_net,
+                ..preferences.clone()
+            })
+        })
+    };
+    let toggle_hide_matured = {
+        let preferences = preferences.clone();
+        Callback::from(move |_: Event| {
+            update_preferences(Preferences {
+                hide_matured: !preferences.hide_matured,
+                ..preferences.clone()
+            })
+        })
+    };
+    let on_search = search.reform(|e: InputEvent| {
+        let input: web_sys::HtmlInputElement = e.target().unwrap().dyn_into().unwrap();
+        input.value()
+    });
+    let now = Utc::now();
+    let hidden = if preferences.hide_matured {
+        investments
+            .iter()
+            .filter(|investment| investment.status(now) == Lifecycle::Matured)
+            .count()
+    } else {
+        0
+    };
+
+    // only ids still listed count, so a row deleted elsewhere drops out of the selection
+    let selection = use_state(Vec::<Thing>::new);
+    let selected: Vec<Thing> = selection
+        .iter()
+        .filter(|id| {
+            investments
+                .iter()
+                .any(|investment| investment.id.as_ref() == Some(*id))
+        })
+        .cloned()
+        .collect();
+    let toggle_selected = {
+        let selection = selection.clone();
+        Callback::from(move |id: Thing| {
+            let mut ids = (*selection).clone();
+            match ids.iter().position(|selected| *selected == id) {
+                Some(position) => {
+                    ids.remove(position);
+                }
+                None => ids.push(id),
+            }
+            selection.set(ids);
+        })
+    };
+    let bulk_actions = bulk_actions(
+        &selected,
+        &selection,
+        delete_investments,
+        archive_investments,
+    );
+
+    let shown = investments.len();
+    let is_empty = investments.is_empty();
     let investments = investments
     .iter()
+    .filter(|investment| !preferences.hide_matured || investment.status(now) != Lifecycle::Matured)
     .map(|investment| {
         // Generate a unique key for each investment everytime so that the DOM can be updated correctly
         let uuid = Uuid::new_v4();
@@ -37,7 +169,17 @@ pub fn investment_list(
             None => "No Thing available".to_string(),
         };
         let key = format!("{}-{}", display_string, uuid);
-        html!(<InvestmentItem key={key} investment={investment.clone()} create_investment={create_investment.clone()} delete_investment={delete_investment} edit_investment={edit_investment} renew_investment={renew_investment} />)
+        // a malformed record gets an inline notice instead of taking the whole table down
+        if let Some(problem) = row_problem(investment) {
+            return html! {
+                <tr key={key} class="border-b dark:border-background-200">
+                    <td colspan="100%" class="px-6 py-4 text-red-600">
+                        {format!("Couldn't show \"{}\": {}", investment.inv_name, problem)}
+                    </td>
+                </tr>
+            };
+        }
+        html!(<InvestmentItem key={key} investment={investment.clone()} create_investment={create_investment.clone()} delete_investment={delete_investment} archive_investment={archive_investment} selected={investment.id.as_ref().is_some_and(|id| selected.contains(id))} toggle_selected={toggle_selected.clone()} move_investment={move_investment} edit_investment={edit_investment} renew_investment={renew_investment} tax_rate_percent={tax_rate_percent} highlighted={highlighted.is_some() && *highlighted == investment.id} />)
     })
     .collect::<Html>();
 
@@ -46,33 +188,175 @@ pub fn investment_list(
             <div class="mx-auto px-4 lg:px-12">
                 <div class="backdrop-blur-sm bg-white/50 dark:bg-black/70 relative shadow-md dark:shadow-white-md rounded-lg overflow-hidden">
                     <div class="flex flex-col md:flex-row items-center justify-between space-y-3 md:space-y-0 md:space-x-4 p-4">
-                        <ExpandableHeader create_investment={create_investment.clone()}/>
+                        <ExpandableHeader create_investment={create_investment.clone()} total={total} investments={investments.clone()} toggles={*create_form_toggles}/>
                     </div>
+                    {bulk_actions}
                     <div class="overflow-x-auto">
                         <table class="w-full text-sm text-left text-text-600">
-                            <thead class="text-xs uppercase bg-background-200">
+                            <thead class="hidden sm:table-header-group text-xs uppercase bg-background-200">
                                 <tr>
+                                    <th scope="col" class="px-2 py-3">
+                                        <span class="sr-only">{"Select"}</span>
+                                    </th>
+                                    <th scope="col" class="px-2 py-3">
+                                        <span class="sr-only">{"Reorder"}</span>
+                                    </th>
                                     <th scope="col" class="px-6 py-3 hidden sm:table-cell">{"Start Date"}</th>
                                     <th scope="col" class="px-6 py-3 hidden lg:table-cell">{"End Date"}</th>
                                     <th scope="col" class="px-6 py-3">{"Investment Name"}</th>
                                     <th scope="col" class="px-6 py-3 hidden lg:table-cell">{"Name"}</th>
                                     <th scope="col" class="px-6 py-3 hidden sm:table-cell">{"Investment Type"}</th>
                                     <th scope="col" class="px-6 py-3 hidden lg:table-cell">{"Return Type"}</th>
-                                    <th scope="col" class="px-6 py-3 hidden lg:table-cell">{"Return Rate"}</th>
-                                    <th scope="col" class="px-6 py-3 hidden lg:table-cell">{"Investment"}</th>
-                                    <th scope="col" class="px-6 py-3">{"Return"}</th>
+                                    <th scope="col" class="px-6 py-3 hidden lg:table-cell text-right">{"Return Rate"}</th>
+                                    <th scope="col" class="px-6 py-3 hidden lg:table-cell text-right">{"Investment"}</th>
+                                    <th scope="col" class="px-6 py-3 text-right">
+                                        {"Return"}
+                                        <button onclick={toggle_net} title={format!("Toggle returns after {TAX_RATE_PERCENT}% tax")} class="ml-1 normal-case font-normal text-accent-600 hover:underline">
+                                            {if show_net { "(net)" } else { "(gross)" }}
+                                        </button>
+                                    </th>
                                     <th scope="col" class="px-6 py-3">
                                         <span >{"Actions"}</span>
                                     </th>
                                 </tr>
                             </thead>
                             {investments}
+                            {if is_empty {
+                                html! {
+                                    <tr>
+                                        <td colspan="100%" class="px-6 py-4 text-center text-text-500">{if query.is_empty() { "No investments yet" } else { "No investments match" }}</td>
+                                    </tr>
+                                }
+                            } else { html! {} }}
                         </table>
                     </div>
                     <nav class="flex flex-col md:flex-row justify-between items-start md:items-center space-y-3 md:space-y-0 p-4" aria-label="Table navigation">
+                        <div class="flex items-center gap-4">
+                            <input type="search" placeholder="Search names" value={query.clone()} oninput={on_search} class="border border-background-300 text-text-950 text-sm rounded-lg p-1.5 bg-background-50" />
+                            {page_size_select(*page, change_page)}
+                            <label class="flex items-center gap-2 text-sm text-text-500">
+                                <input type="checkbox" checked={preferences.hide_matured} onchange={toggle_hide_matured} />
+                                {if hidden > 0 { format!("Hide matured ({hidden} hidden)") } else { "Hide matured".to_string() }}
+                            </label>
+                        </div>
+                        {page_buttons(*page, shown, change_page)}
                     </nav>
                 </div>
             </div>
         </section>
     }
 }
+
+/// Archive and delete buttons for the rows ticked, shown while any are.
+/// Either action clears the selection once it is sent.
+fn bulk_actions(
+    selected: &[Thing],
+    selection: &UseStateHandle<Vec<Thing>>,
+    delete_investments: &Callback<Vec<Thing>>,
+    archive_investments: &Callback<Vec<Thing>>,
+) -> Html {
+    if selected.is_empty() {
+        return html! {};
+    }
+    let count = selected.len();
+    let on_archive = {
+        let selected = selected.to_vec();
+        let selection = selection.clone();
+        let archive_investments = archive_investments.clone();
+        Callback::from(move |_: MouseEvent| {
+            archive_investments.emit(selected.clone());
+            selection.set(Vec::new());
+        })
+    };
+    let on_delete = {
+        let selected = selected.to_vec();
+        let selection = selection.clone();
+        let delete_investments = delete_investments.clone();
+        Callback::from(move |_: MouseEvent| {
+            if confirm(&format!("Delete {count} investments?")) {
+                delete_investments.emit(selected.clone());
+                selection.set(Vec::new());
+            }
+        })
+    };
+    let on_clear = {
+        let selection = selection.clone();
+        Callback::from(move |_: MouseEvent| selection.set(Vec::new()))
+    };
+
+    html! {
+        <div class="flex items-center gap-4 px-4 py-2 text-sm bg-background-100">
+            <span>{format!("{count} selected")}</span>
+            <button onclick={on_archive} class="font-medium text-text-600 hover:underline">{"Archive"}</button>
+            <button onclick={on_delete} class="font-medium text-red-600 dark:text-red-500 hover:underline">{"Delete"}</button>
+            <button onclick={on_clear} class="text-text-500 hover:underline">{"Clear"}</button>
+        </div>
+    }
+}
+
+/// Why an investment can't be rendered as a regular row, if it can't.
+fn row_problem(investment: &Investment) -> Option<&'static str> {
+    if investment.id.is_none() {
+        return Some("it has no id");
+    }
+    for date in [investment.start_date, investment.end_date] {
+        match date {
+            None => return Some("a date is missing"),
+            Some(date) if !is_reasonable_date(&date) => return Some("a date is out of range"),
+            Some(_) => {}
+        }
+    }
+    if investment.inv_amount < 0 || investment.return_amount < 0 {
+        return Some("an amount is negative");
+    }
+
+    None
+}
+
+/// Rows-per-page dropdown; changing it reloads from the first page.
+fn page_size_select(page: Page, change_page: &Callback<(usize, usize)>) -> Html {
+    let mut sizes = PAGE_SIZES.to_vec();
+    if page.limit > 0 && !sizes.contains(&page.limit) {
+        sizes.push(page.limit);
+        sizes.sort_unstable();
+    }
+    let on_change = change_page.reform(|e: Event| {
+        let limit = e
+            .target()
+            .and_then(|target| target.dyn_into::<HtmlSelectElement>().ok())
+            .and_then(|select| select.value().parse().ok())
+            .unwrap_or(PAGE_SIZES[0]);
+        (limit, 0)
+    });
+
+    html! {
+        <label class="flex items-center gap-2 text-sm text-text-500">
+            {"Rows per page"}
+            <select onchange={on_change} class="border border-background-300 text-text-950 text-sm rounded-lg p-1.5 bg-background-50">
+                { for sizes.into_iter().map(|size| html! {
+                    <option value={size.to_string()} selected={size == page.limit}>{size}</option>
+                }) }
+            </select>
+        </label>
+    }
+}
+
+/// Previous/next buttons with the range of rows on show.
+fn page_buttons(page: Page, shown: usize, change_page: &Callback<(usize, usize)>) -> Html {
+    let on_previous = change_page
+        .reform(move |_: MouseEvent| (page.limit, page.offset.saturating_sub(page.limit)));
+    let on_next = change_page.reform(move |_: MouseEvent| (page.limit, page.offset + page.limit));
+    let range = if shown == 0 {
+        "0".to_string()
+    } else {
+        format!("{}-{}", page.offset + 1, page.offset + shown)
+    };
+
+    html! {
+        <div class="flex items-center gap-3 text-sm text-text-500">
+            <span>{range}</span>
+            <button onclick={on_previous} disabled={page.offset == 0} class="px-3 py-1 rounded bg-background-200 disabled:opacity-50">{"Previous"}</button>
+            <button onclick={on_next} disabled={!page.has_more} class="px-3 py-1 rounded bg-background-200 disabled:opacity-50">{"Next"}</button>
+        </div>
+    }
+}
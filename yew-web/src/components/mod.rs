@@ -23,3 +23,9 @@ pub mod inv_list;
 
 /// This module contains the switcher component.
 pub mod switcher;
+
+/// This module contains the theme context and its provider.
+pub mod theme;
+
+/// This module contains the investment status badge.
+pub mod status_badge;
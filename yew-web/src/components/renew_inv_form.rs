@@ -1,13 +1,15 @@
-use std::collections::HashMap;
-
 use chrono::{DateTime, NaiveDate, TimeZone, Utc};
 use web_sys::wasm_bindgen::JsCast;
 use web_sys::{HtmlSelectElement, MouseEvent};
 use yew::events::{Event, InputEvent};
-use yew::{html, Callback, Component, Html, Properties};
+use yew::{html, Callback, Component, Html, NodeRef, Properties};
 
-use super::base_inv_form::BaseFormComponent;
-use types::{InvStatus, Investment};
+use super::base_inv_form::{BaseFormComponent, INV_TYPE_HELP, RETURN_RATE_HELP, RETURN_TYPE_HELP};
+use crate::format::format_rate;
+use crate::inv_api::fetch_investor_names;
+use crate::platform;
+use crate::unload_guard::UnloadGuard;
+use types::Investment;
 
 #[derive(Properties, PartialEq, Clone)]
 pub struct RenewInvForm {
@@ -15,7 +17,10 @@ pub struct RenewInvForm {
     show_renew_confirmation: bool,
     props: RenewInvFormProps,
     base: BaseFormComponent,
+    unload_guard: UnloadGuard,
     renew_investment: Investment,
+    confirm_button: NodeRef,
+    focus_confirm: bool,
 }
 
 #[derive(Properties, PartialEq, Clone)]
@@ -28,6 +33,7 @@ pub struct RenewInvFormProps {
 pub enum Form {
     Update(String, String),
     UpdateDate(String, Option<DateTime<Utc>>),
+    NameSuggestions(Vec<String>),
     Confirm,
     Cancel,
     Renew,
@@ -38,6 +44,11 @@ impl Component for RenewInvForm {
     type Properties = RenewInvFormProps;
 
     fn create(ctx: &yew::Context<Self>) -> Self {
+        // suggestions are a convenience, so a failed fetch just leaves them empty
+        ctx.link().send_future(async {
+            Form::NameSuggestions(fetch_investor_names().await.unwrap_or_default())
+        });
+
         Self {
             form_changed: false,
             show_renew_confirmation: false,
@@ -53,17 +64,20 @@ impl Component for RenewInvForm {
                 inv_status: None,
                 start_date: ctx.props().old_investment.end_date,
                 end_date: None,
-                created_at: None,
-                updated_at: None,
+                monthly_amount: ctx.props().old_investment.monthly_amount,
+                installments: ctx.props().old_investment.installments,
+                lock_in_days: ctx.props().old_investment.lock_in_days,
+                ..Default::default()
             },
             props: RenewInvFormProps {
                 renew_investment: ctx.props().renew_investment.clone(),
                 old_investment: ctx.props().old_investment.clone(),
                 on_renew: ctx.props().on_renew.clone(),
             },
-            base: BaseFormComponent {
-                error_messages: HashMap::new(),
-            },
+            base: BaseFormComponent::default(),
+            unload_guard: UnloadGuard::default(),
+            confirm_button: NodeRef::default(),
+            focus_confirm: false,
         }
     }
 
@@ -73,43 +87,72 @@ impl Component for RenewInvForm {
                 self.base
                     .update_field(&mut self.renew_investment, &field, value);
                 self.form_changed = true;
+                self.unload_guard.set_dirty(true);
             }
             Form::UpdateDate(field, date) => {
                 self.base
                     .update_date_field(&mut self.renew_investment, &field, date);
                 self.form_changed = true;
+                self.unload_guard.set_dirty(true);
+            }
+            Form::NameSuggestions(names) => {
+                self.base.name_suggestions = names;
             }
             Form::Confirm => {
+                self.show_renew_confirmation = false;
+                // only a save that went out clears the pending edits
                 if self.save_form() {
+                    self.form_changed = false;
+                    self.unload_guard.set_dirty(false);
                     self.props.on_renew.emit(());
                 }
             }
             Form::Cancel => {
+                // back to the form with the edits and form_changed untouched
                 self.show_renew_confirmation = false;
             }
             Form::Renew => {
                 self.show_renew_confirmation = true;
+                self.focus_confirm = true;
             }
         }
         true
     }
 
+    fn rendered(&mut self, _ctx: &yew::Context<Self>, _first_render: bool) {
+        // the confirm button only exists once the modal has been rendered
+        if self.focus_confirm {
+            self.focus_confirm = false;
+            platform::focus_unless_editing(&self.confirm_button);
+        }
+    }
+
     fn view(&self, ctx: &yew::Context<Self>) -> Html {
+        // the original closes when its successor starts, or now if that isn't set yet
+        let closes_at = self.renew_investment.start_date.unwrap_or_else(Utc::now);
+        let old_investment = &self.props.old_investment;
+        let lock_in_warning = old_investment.is_locked_in(closes_at).then(|| {
+            let ends = old_investment
+                .lock_in_ends()
+                .map(|ends| ends.format("%d-%m-%Y").to_string())
+                .unwrap_or_default();
+            format!("This investment is locked in until {ends}. Renewing before then may cost an early withdrawal penalty.")
+        });
+
         html! {
             <div class="mx-auto w-full relative">
                 <form>
                     <div class="grid gap-6 mb-6 md:grid-cols-2 lg:grid-cols-3 text-text-950">
-                        { self.date_field(ctx, "start-date", &self.renew_investment.start_date.map(|d| d.format("%Y-%m-%d").to_string()).unwrap_or_default()) }
-                        { self.date_field(ctx, "end-date", &self.renew_investment.end_date.map(|d| d.format("%Y-%m-%d").to_string()).unwrap_or_default()) }
-                        { self.input_field(ctx, "inv-name", "text", &self.renew_investment.inv_name) }
-                        { self.input_field(ctx, "name", "text", &self.renew_investment.name) }
+                        { self.input_field(ctx, "inv-name", "text", &self.renew_investment.inv_name, None) }
+                        { self.name_field(ctx, &self.renew_investment.name) }
                         { self.select_field(ctx, "inv-type", &self.renew_investment.inv_type,
                             html! {
                                 <>
                                     <option value="FD" selected={self.renew_investment.inv_type == "FD"}>{"FD"}</option>
                                     <option value="RD" selected={self.renew_investment.inv_type == "RD"}>{"RD"}</option>
                                 </>
-                            }
+                            },
+                            Some(INV_TYPE_HELP)
                         ) }
                         { self.select_field(ctx, "return-type", &self.renew_investment.return_type,
                             html! {
@@ -117,11 +160,14 @@ impl Component for RenewInvForm {
                                     <option value="Ordinary" selected={self.renew_investment.return_type == "Ordinary"}>{"Ordinary"}</option>
                                     <option value="Culmulative" selected={self.renew_investment.return_type == "Culmulative"}>{"Culmulative"} </option>
                                 </>
-                            }
+                            },
+                            Some(RETURN_TYPE_HELP)
                         ) }
-                        { self.input_field(ctx, "return-amount", "number", &self.renew_investment.return_amount.to_string()) }
-                        { self.input_field(ctx, "inv-amount", "number", &self.renew_investment.inv_amount.to_string()) }
-                        { self.input_field(ctx, "return-rate", "number", &self.renew_investment.return_rate.to_string()) }
+                        { self.amount_field(ctx, "inv-amount", self.renew_investment.inv_amount, None) }
+                        { self.amount_field(ctx, "return-amount", self.renew_investment.return_amount, None) }
+                        { self.input_field(ctx, "return-rate", "number", &format_rate(self.renew_investment.return_rate), Some(RETURN_RATE_HELP)) }
+                        { self.date_field(ctx, "start-date", &self.renew_investment.start_date.map(|d| d.format("%Y-%m-%d").to_string()).unwrap_or_default()) }
+                        { self.date_field(ctx, "end-date", &self.renew_investment.end_date.map(|d| d.format("%Y-%m-%d").to_string()).unwrap_or_default()) }
                         <button type="submit" disabled={!self.form_changed}
                             onclick={ctx.link().callback(|e: MouseEvent| {
                                 // prevent the webpage from moving to top when the button is clicked
@@ -138,8 +184,11 @@ impl Component for RenewInvForm {
                         <div class="absolute inset-0 flex items-center justify-center bg-white dark:bg-black bg-opacity-80 dark:bg-opacity-70">
                             <div class="bg-background-50 p-4 rounded text-text-950">
                                 <p class="mb-2">{"Are you sure you want to renew this Investment?"}</p>
+                                {if let Some(warning) = &lock_in_warning {
+                                    html! { <p class="mb-2 text-sm text-red-600">{warning}</p> }
+                                } else { html! {} }}
                                 <div class="flex justify-center">
-                                    <button onclick={ctx.link().callback(|_| Form::Confirm)} class="bg-red-500 px-4 py-2 mr-1 rounded">{"Confirm"}</button>
+                                    <button ref={self.confirm_button.clone()} onclick={ctx.link().callback(|_| Form::Confirm)} class="bg-red-500 px-4 py-2 mr-1 rounded">{if lock_in_warning.is_some() { "Renew anyway" } else { "Confirm" }}</button>
                                     <button onclick={ctx.link().callback(|_| Form::Cancel)} class="bg-background-500 px-4 py-2 ml-1 rounded">{"Cancel"}</button>
                                 </div>
                             </div>
@@ -158,6 +207,37 @@ impl RenewInvForm {
         field_id: &str,
         field_type: &str,
         field_value: &str,
+        help_text: Option<&str>,
+    ) -> Html {
+        let field_id_str = field_id.to_string();
+        let on_input = ctx.link().callback(move |e: InputEvent| {
+            let input: web_sys::HtmlInputElement = e.target().unwrap().dyn_into().unwrap();
+            Form::Update(field_id_str.clone(), input.value())
+        });
+        self.base.input_field(
+            field_id,
+            field_type,
+            field_value,
+            on_input,
+            help_text,
+            NodeRef::default(),
+        )
+    }
+
+    fn name_field(&self, ctx: &yew::Context<Self>, field_value: &str) -> Html {
+        let on_input = ctx.link().callback(move |e: InputEvent| {
+            let input: web_sys::HtmlInputElement = e.target().unwrap().dyn_into().unwrap();
+            Form::Update("name".to_string(), input.value())
+        });
+        self.base.name_field(field_value, on_input)
+    }
+
+    fn amount_field(
+        &self,
+        ctx: &yew::Context<Self>,
+        field_id: &str,
+        amount: i64,
+        help_text: Option<&str>,
     ) -> Html {
         let field_id_str = field_id.to_string();
         let on_input = ctx.link().callback(move |e: InputEvent| {
@@ -165,7 +245,7 @@ impl RenewInvForm {
             Form::Update(field_id_str.clone(), input.value())
         });
         self.base
-            .input_field(field_id, field_type, field_value, on_input)
+            .amount_field(field_id, amount, on_input, help_text)
     }
 
     fn select_field(
@@ -174,6 +254,7 @@ impl RenewInvForm {
         field_id: &str,
         field_value: &str,
         options: Html,
+        help_text: Option<&str>,
     ) -> Html {
         let field_id_str = field_id.to_string();
         let on_change = ctx.link().callback(move |e: Event| {
@@ -183,7 +264,7 @@ impl RenewInvForm {
             Form::Update(field_id_str.clone(), value)
         });
         self.base
-            .select_field(field_id, field_value, options, on_change)
+            .select_field(field_id, field_value, options, on_change, help_text)
     }
 
     fn date_field(&self, ctx: &yew::Context<Self>, field_id: &str, field_value: &str) -> Html {
@@ -200,7 +281,22 @@ impl RenewInvForm {
             Form::UpdateDate(field_id_str.clone(), date)
         });
 
-        self.base.date_field(field_id, field_value, on_input)
+        let quick_picks = if field_id == "end-date" {
+            let on_pick = ctx
+                .link()
+                .callback(|end| Form::UpdateDate("end-date".to_string(), Some(end)));
+            self.base
+                .end_date_quick_picks(self.renew_investment.start_date, on_pick)
+        } else {
+            html! {}
+        };
+        self.base.date_field(
+            field_id,
+            field_value,
+            on_input,
+            NodeRef::default(),
+            quick_picks,
+        )
     }
 
     fn save_form(&mut self) -> bool {
@@ -208,28 +304,7 @@ impl RenewInvForm {
         let is_valid = self.base.validate_form(&mut self.renew_investment);
 
         if is_valid {
-            // add inv_status to renewed investment with the id of the old investment
-            // and status as "renewed"
-            self.renew_investment.inv_status = Some(InvStatus {
-                id: self.props.old_investment.id.clone(),
-                status: "renewed".to_string(),
-            });
-
-            // update the old investment with status as "closed"
-            let mut old_investment = self.props.old_investment.clone();
-            if let Some(old_status) = &old_investment.inv_status {
-                old_investment.inv_status = Some(InvStatus {
-                    id: old_status.id.clone(), // keep the id same as before
-                    status: "closed".to_string(),
-                });
-            } else {
-                // Handle the case where inv_status is None
-                old_investment.inv_status = Some(InvStatus {
-                    id: None,
-                    status: "closed".to_string(),
-                });
-            }
-
+            // the server links the two investments and closes the old one
             self.props.renew_investment.emit((
                 self.props.old_investment.clone(),
                 self.renew_investment.clone(),
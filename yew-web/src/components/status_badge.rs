@@ -0,0 +1,28 @@
+use yew::{function_component, html, use_context, Html, Properties};
+
+use super::theme::{Theme, Tone};
+use types::Lifecycle;
+
+#[derive(Properties, PartialEq)]
+pub struct StatusBadgeProps {
+    pub status: Lifecycle,
+}
+
+#[function_component(StatusBadge)]
+pub fn status_badge(props: &StatusBadgeProps) -> Html {
+    let theme = use_context::<Theme>();
+
+    let (label, tone) = match props.status {
+        Lifecycle::Active => ("Active", Tone::Positive),
+        Lifecycle::Matured => ("Matured", Tone::Neutral),
+        Lifecycle::Renewed => ("Renewed", Tone::Positive),
+        Lifecycle::Closed => ("Closed", Tone::Warning),
+    };
+    // outside a provider, fall back to the page's default dark palette
+    let dark = theme.as_ref().map_or(true, |theme| theme.dark);
+    let palette = tone.badge_class(dark);
+
+    html! {
+        <span class={format!("{palette} inline-block px-2 py-0.5 rounded text-xs font-medium")}>{label.to_string()}</span>
+    }
+}
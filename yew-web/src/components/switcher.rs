@@ -1,34 +1,18 @@
-use web_sys::{wasm_bindgen::JsCast, window, Element};
-use yew::{function_component, html, use_state, Callback, Html};
+use yew::{function_component, html, use_context, Html};
+
+use super::theme::Theme;
 
 #[function_component(DarkModeContent)]
 pub fn dark_mode_content() -> Html {
-    let dark_mode = use_state(|| true);
-
-    let toggle_dark_mode = {
-        let dark_mode = dark_mode.clone();
-        Callback::from(move |_| {
-            let dark_mode_enabled = !*dark_mode;
-            dark_mode.set(dark_mode_enabled);
-
-            if let Some(window) = window() {
-                if let Some(document) = window.document() {
-                    let root_element = document.document_element().unwrap();
-                    let root_element = root_element.dyn_into::<Element>().unwrap();
-                    if dark_mode_enabled {
-                        root_element.class_list().add_1("dark").unwrap();
-                    } else {
-                        root_element.class_list().remove_1("dark").unwrap();
-                    }
-                }
-            }
-        })
+    let Some(theme) = use_context::<Theme>() else {
+        return html! {};
     };
+    let toggle_dark_mode = theme.toggle.reform(|_| ());
 
     html! {
         <div>
             <button onclick={toggle_dark_mode} class="transition-colors duration-1000">
-                {if *dark_mode {
+                {if theme.dark {
                     html! {
                         <div>
                             <svg class="animate-icon" xmlns="http://www.w3.org/2000/svg" width="24" height="24" color="white" viewBox="0 0 24 24" fill="none" stroke="currentColor" stroke-width="2" stroke-linecap="round" stroke-linejoin="round">
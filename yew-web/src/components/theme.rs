@@ -0,0 +1,87 @@
+use yew::{
+    function_component, html, use_context, use_effect_with_deps, Callback, Children,
+    ContextProvider, Html, Properties,
+};
+
+use crate::platform;
+use crate::preferences::{Preferences, PreferencesContext};
+
+/// The current colour scheme, shared through a Yew context so components
+/// pick palette variants from it instead of hardcoding colours.
+#[derive(Clone, PartialEq)]
+pub struct Theme {
+    pub dark: bool,
+    pub toggle: Callback<()>,
+}
+
+/// What a badge is signalling, independent of the colours used to show it.
+#[derive(Clone, Copy, PartialEq)]
+pub enum Tone {
+    Neutral,
+    Positive,
+    Warning,
+}
+
+impl Tone {
+    /// Badge background and text classes for this tone on a light or dark page.
+    pub fn badge_class(self, dark: bool) -> &'static str {
+        match (self, dark) {
+            (Tone::Neutral, false) => "bg-gray-100 text-gray-800",
+            (Tone::Neutral, true) => "bg-gray-700 text-gray-200",
+            (Tone::Positive, false) => "bg-green-100 text-green-800",
+            (Tone::Positive, true) => "bg-green-900 text-green-200",
+            (Tone::Warning, false) => "bg-yellow-100 text-yellow-800",
+            (Tone::Warning, true) => "bg-yellow-900 text-yellow-200",
+        }
+    }
+}
+
+/// Stored choice first, then the OS preference, then dark (the page's default).
+fn dark_mode(preferences: &Preferences) -> bool {
+    preferences
+        .dark_mode
+        .unwrap_or_else(|| platform::prefers_dark_mode().unwrap_or(true))
+}
+
+#[derive(Properties, PartialEq)]
+pub struct ThemeProviderProps {
+    pub children: Children,
+}
+
+#[function_component(ThemeProvider)]
+pub fn theme_provider(props: &ThemeProviderProps) -> Html {
+    let context = use_context::<PreferencesContext>();
+    let preferences = context
+        .as_ref()
+        .map(|context| context.preferences.clone())
+        .unwrap_or_default();
+    let dark_mode = dark_mode(&preferences);
+
+    use_effect_with_deps(
+        move |_| {
+            platform::set_dark_class(dark_mode);
+            || {}
+        },
+        dark_mode,
+    );
+
+    let toggle = Callback::from(move |_| {
+        if let Some(context) = &context {
+            context.update.emit(Preferences {
+                dark_mode: Some(!dark_mode),
+                ..preferences.clone()
+            });
+        }
+    });
+
+    let theme = Theme {
+        dark: dark_mode,
+        toggle,
+    };
+
+    html! {
+        <ContextProvider<Theme> context={theme}>
+            { props.children.clone() }
+        </ContextProvider<Theme>>
+    }
+}
@@ -1,3 +1,5 @@
+use std::cell::Cell;
+
 use gloo_dialogs::alert;
 use serde::{Deserialize, Serialize};
 use surrealdb::sql::{Id, Thing};
@@ -7,10 +9,18 @@ use yew::UseReducerHandle;
 use crate::{inv_api::*, state::*};
 use types::*;
 
+thread_local! {
+    // controllers are rebuilt on every render, so the counter can't live on one
+    static LOAD_GENERATION: Cell<u32> = Cell::new(0);
+}
+
 pub struct InvestmentController {
     state: UseReducerHandle<InvestmentState>,
 }
 
+/// Fields the server sets on renewal and refuses in an edit.
+const SERVER_OWNED_FIELDS: [&str; 2] = ["renewed_from", "renewed_to"];
+
 #[derive(Serialize, Deserialize)]
 pub struct TempThing {
     pub tb: String,
@@ -22,18 +32,49 @@ impl InvestmentController {
         InvestmentController { state }
     }
 
-    pub fn init_investments(&self) {
+    /// Loads the first page, at the server's default page size unless one is given.
+    pub fn init_investments(&self, page_size: Option<usize>) {
+        self.load_page(page_size, 0);
+    }
+
+    /// Loads the page of `limit` rows starting at `offset`. When loads overlap
+    /// the last one asked for wins, whichever answers first.
+    pub fn load_page(&self, limit: Option<usize>, offset: usize) {
+        let generation = LOAD_GENERATION.with(|latest| {
+            let generation = latest.get().wrapping_add(1);
+            latest.set(generation);
+            generation
+        });
         let investments = self.state.clone();
 
         spawn_local(async move {
-            let fetched_investments = fetch_investments().await;
+            let fetched_investments = fetch_investments(limit, offset).await;
+            // a newer load was asked for meanwhile; its answer is the one to show
+            if LOAD_GENERATION.with(Cell::get) != generation {
+                return;
+            }
             match fetched_investments {
-                Ok(ft) => investments.dispatch(InvestmentAction::Set(ft)),
+                Ok((ft, page)) => {
+                    investments.dispatch(InvestmentAction::Set(ft));
+                    investments.dispatch(InvestmentAction::SetPage(page));
+                }
                 Err(e) => alert(&e.to_string()),
             }
         });
     }
 
+    /// Drops every loaded investment from state; nothing is deleted on the server.
+    pub fn clear_investments(&self) {
+        self.state.dispatch(InvestmentAction::Clear);
+    }
+
+    /// Drops what is loaded and loads the first page again, e.g. after
+    /// another tab or client changed the list.
+    pub fn reset_investments(&self, page_size: Option<usize>) {
+        self.clear_investments();
+        self.init_investments(page_size);
+    }
+
     pub fn create_investment(&self, inv: Investment) {
         let investments = self.state.clone();
 
@@ -51,6 +92,34 @@ impl InvestmentController {
     pub fn edit_investment(&self, inv: Investment) {
         let investments = self.state.clone();
 
+        // send only what changed when we know the investment as it was loaded
+        let original = investments
+            .investments
+            .iter()
+            .find(|investment| investment.id == inv.id)
+            .cloned();
+        if let (Some(original), Some(id)) = (original, inv.id.clone()) {
+            let mut fields = diff_investment(&original, &inv);
+            if fields.is_empty() {
+                return;
+            }
+            // the version edited, so the server refuses to overwrite a save made since
+            if let Some(updated_at) = original.updated_at {
+                fields.insert("updated_at".to_string(), serde_json::json!(updated_at));
+            }
+
+            spawn_local(async move {
+                let fields = serde_json::Value::Object(fields);
+                let response = patch_investment(id.to_string(), fields.to_string()).await;
+
+                match response {
+                    Ok(investment) => investments.dispatch(InvestmentAction::Edit(investment)),
+                    Err(e) => alert(&e.to_string()),
+                }
+            });
+            return;
+        }
+
         spawn_local(async move {
             // this is a workaround for the fact that we can't serialize a Thing
             // directly. We need to serialize a struct that contains the Thing's
@@ -69,6 +138,9 @@ impl InvestmentController {
 
             if let Some(obj1) = inv.as_object_mut() {
                 obj1.insert("id".to_string(), temp_id.clone());
+                for field in SERVER_OWNED_FIELDS {
+                    obj1.remove(field);
+                }
             }
             let response = edit_investment(inv.to_string()).await;
 
@@ -92,81 +164,129 @@ impl InvestmentController {
             let response = delete_investment(json_id.to_string()).await;
 
             match response {
-                Ok(af) if af.id == id => investments.dispatch(InvestmentAction::Delete(id.clone())),
-                Ok(_) => alert("Did not get a response"),
+                Ok(_) => investments.dispatch(InvestmentAction::Delete(id.clone())),
+                Err(ApiError::Status(404, _)) => {
+                    alert("This investment had already been deleted");
+                    investments.dispatch(InvestmentAction::Delete(id.clone()))
+                }
                 Err(e) => alert(&e.to_string()),
             }
         });
     }
 
-    pub fn renew_investment(&self, old_inv: Investment, renew_inv: Investment) {
+    pub fn archive_investment(&self, id: Thing) {
         let investments = self.state.clone();
 
         spawn_local(async move {
-            // add renew investment
+            let response = archive_investment(id.to_string()).await;
 
-            // this is a workaround for the fact that we can't serialize a Thing
-            // directly. We need to serialize a struct that contains the Thing's
-            // table name and id and then insert that into the Investment object
-            // and then serialize the Investment object.
-            // TODO: update surrealdb to allow serialization of a Thing after figuring out dependency issues
-            let inv_status = match renew_inv.inv_status.clone() {
-                Some(inv_status) => inv_status.id.clone(),
-                None => None,
-            };
-            let temp_id = match inv_status {
-                Some(id) => serialize_thing(id),
-                None => TempThing {
-                    tb: "".to_string(),
-                    id: Id::Number(0),
-                },
-            };
-            let temp_id = serde_json::json!(temp_id);
-
-            let mut inv_status = serde_json::json!(renew_inv.inv_status);
-
-            if let Some(obj1) = inv_status.as_object_mut() {
-                obj1.insert("id".to_string(), temp_id.clone());
+            // archived investments drop out of the default list
+            match response {
+                Ok(_) => investments.dispatch(InvestmentAction::Delete(id)),
+                Err(e) => alert(&e.to_string()),
             }
+        });
+    }
+
+    /// Deletes every investment in `ids` with one request and drops them from
+    /// the list in one go.
+    pub fn delete_investments(&self, ids: Vec<Thing>) {
+        let investments = self.state.clone();
 
-            let mut renew_inv = serde_json::json!(renew_inv);
+        spawn_local(async move {
+            let json_ids =
+                serde_json::json!(ids.iter().map(ToString::to_string).collect::<Vec<_>>());
+            let response = delete_investments(json_ids.to_string()).await;
 
-            if let Some(obj1) = renew_inv.as_object_mut() {
-                obj1.insert("inv_status".to_string(), inv_status.clone());
+            // ids deleted elsewhere meanwhile aren't counted, but are gone all the same
+            match response {
+                Ok(_) => investments.dispatch(InvestmentAction::RemoveMany(ids)),
+                Err(e) => alert(&e.to_string()),
             }
+        });
+    }
 
-            log::info!("inv: {}", renew_inv);
-            let response = create_investment(renew_inv.to_string()).await;
+    /// Archives every investment in `ids` with one request; like a single
+    /// archive, they drop out of the default list, all in one go.
+    pub fn archive_investments(&self, ids: Vec<Thing>) {
+        let investments = self.state.clone();
+
+        spawn_local(async move {
+            let json_ids =
+                serde_json::json!(ids.iter().map(ToString::to_string).collect::<Vec<_>>());
+            let response = archive_investments(json_ids.to_string()).await;
 
             match response {
-                Ok(investment) => investments.dispatch(InvestmentAction::Add(investment)),
+                Ok(_) => investments.dispatch(InvestmentAction::RemoveMany(ids)),
                 Err(e) => alert(&e.to_string()),
             }
+        });
+    }
 
-            // update old investment
+    /// Moves the investment with id `dragged` to just before `target` and saves the new order.
+    pub fn move_investment(&self, dragged: String, target: Thing) {
+        let investments = self.state.clone();
 
-            // this is a workaround for the fact that we can't serialize a Thing
-            // directly. We need to serialize a struct that contains the Thing's
-            // table name and id and then insert that into the Investment object
-            // and then serialize the Investment object.
-            // TODO: update surrealdb to allow serialization of a Thing after figuring out dependency issues
-            let temp_id = match old_inv.id.clone() {
-                Some(id) => serialize_thing(id),
-                None => TempThing {
-                    tb: "".to_string(),
-                    id: Id::Number(0),
-                },
-            };
-            let temp_id = serde_json::json!(temp_id);
-            let mut old_inv = serde_json::json!(old_inv);
+        let mut reordered = investments.investments.clone();
+        let is_dragged = |investment: &Investment| {
+            investment.id.as_ref().map(ToString::to_string).as_deref() == Some(dragged.as_str())
+        };
+        let Some(moved) = reordered
+            .iter()
+            .position(is_dragged)
+            .and_then(|from| reordered.remove(from))
+        else {
+            return;
+        };
+        let to = reordered
+            .iter()
+            .position(|investment| investment.id.as_ref() == Some(&target))
+            .unwrap_or(reordered.len());
+        reordered.insert(to, moved);
 
-            if let Some(obj1) = old_inv.as_object_mut() {
-                obj1.insert("id".to_string(), temp_id.clone());
+        // same numbering the server assigns, so the list doesn't jump on the next fetch
+        let offset = investments.page.offset;
+        for (position, investment) in reordered.iter_mut().enumerate() {
+            investment.sort_order = (offset + position) as i64 + 1;
+        }
+        let ids: Vec<String> = reordered
+            .iter()
+            .filter_map(|investment| investment.id.as_ref().map(ToString::to_string))
+            .collect();
+        investments.dispatch(InvestmentAction::Set(reordered));
+
+        spawn_local(async move {
+            let response = reorder_investments(serde_json::json!(ids).to_string(), offset).await;
+
+            if let Err(e) = response {
+                alert(&e.to_string());
             }
-            let response = edit_investment(old_inv.to_string()).await;
+        });
+    }
+
+    pub fn renew_investment(&self, old_inv: Investment, renew_inv: Investment) {
+        let investments = self.state.clone();
+
+        let Some(old_id) = old_inv.id else {
+            alert("Only a saved investment can be renewed");
+            return;
+        };
+
+        spawn_local(async move {
+            // a fresh renewal has no id or status, so no Thing needs the serialization workaround
+            let renew_inv = serde_json::json!(renew_inv);
+
+            // the server creates the successor and closes the original in one go
+            let response = renew_investment(old_id.to_string(), renew_inv.to_string()).await;
 
             match response {
-                Ok(investment) => investments.dispatch(InvestmentAction::Edit(investment)),
+                Ok(renewal) => {
+                    // the closed original is already listed, so it's updated in place
+                    investments.dispatch(InvestmentAction::AddMany(vec![
+                        renewal.renewed,
+                        renewal.closed,
+                    ]));
+                }
                 Err(e) => alert(&e.to_string()),
             }
         });
@@ -179,3 +299,108 @@ fn serialize_thing(thing: Thing) -> TempThing {
         id: thing.id,
     }
 }
+
+/// Same workaround as above for the Thing nested in an investment's status.
+fn serialize_inv_status(inv_status: Option<&InvStatus>) -> serde_json::Value {
+    let temp_id = match inv_status.and_then(|inv_status| inv_status.id.clone()) {
+        Some(id) => serialize_thing(id),
+        None => TempThing {
+            tb: "".to_string(),
+            id: Id::Number(0),
+        },
+    };
+    let temp_id = serde_json::json!(temp_id);
+
+    let mut inv_status = serde_json::json!(inv_status);
+
+    if let Some(obj1) = inv_status.as_object_mut() {
+        obj1.insert("id".to_string(), temp_id);
+    }
+
+    inv_status
+}
+
+/// Builds a patch holding only the fields of `edited` that differ from
+/// `original`, with `null` for the ones it cleared.
+pub fn diff_investment(
+    original: &Investment,
+    edited: &Investment,
+) -> serde_json::Map<String, serde_json::Value> {
+    let original = serde_json::json!(original);
+    let edited_json = serde_json::json!(edited);
+    let mut fields = serde_json::Map::new();
+
+    if let (Some(original), Some(edited_fields)) = (original.as_object(), edited_json.as_object()) {
+        for (key, value) in edited_fields {
+            if key == "id"
+                || SERVER_OWNED_FIELDS.contains(&key.as_str())
+                || original.get(key) == Some(value)
+            {
+                continue;
+            }
+            let value = match key.as_str() {
+                "inv_status" => serialize_inv_status(edited.inv_status.as_ref()),
+                _ => value.clone(),
+            };
+            fields.insert(key.clone(), value);
+        }
+        // a field cleared to `None` is left out of `edited` rather than sent as null
+        for key in original.keys() {
+            if key != "id"
+                && !SERVER_OWNED_FIELDS.contains(&key.as_str())
+                && !edited_fields.contains_key(key)
+            {
+                fields.insert(key.clone(), serde_json::Value::Null);
+            }
+        }
+    }
+
+    fields
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn inv() -> Investment {
+        Investment {
+            inv_name: "HDFC FD".into(),
+            inv_type: "FD".into(),
+            return_rate: 725,
+            inv_amount: 100_000,
+            lock_in_days: Some(90),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn an_unchanged_investment_sends_nothing() {
+        assert!(diff_investment(&inv(), &inv()).is_empty());
+    }
+
+    #[test]
+    fn only_the_changed_field_is_sent() {
+        let edited = Investment {
+            inv_amount: 150_000,
+            ..inv()
+        };
+
+        let patch = diff_investment(&inv(), &edited);
+
+        assert_eq!(patch.len(), 1);
+        assert_eq!(patch["inv_amount"], serde_json::json!(150_000));
+    }
+
+    #[test]
+    fn a_cleared_optional_field_is_sent_as_null() {
+        let edited = Investment {
+            lock_in_days: None,
+            ..inv()
+        };
+
+        let patch = diff_investment(&inv(), &edited);
+
+        assert_eq!(patch.len(), 1);
+        assert_eq!(patch["lock_in_days"], serde_json::Value::Null);
+    }
+}
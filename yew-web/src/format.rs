@@ -0,0 +1,233 @@
+//! Helpers for displaying amounts the way they are written in India.
+
+use types::Investment;
+
+const ONES: [&str; 20] = [
+    "Zero",
+    "One",
+    "Two",
+    "Three",
+    "Four",
+    "Five",
+    "Six",
+    "Seven",
+    "Eight",
+    "Nine",
+    "Ten",
+    "Eleven",
+    "Twelve",
+    "Thirteen",
+    "Fourteen",
+    "Fifteen",
+    "Sixteen",
+    "Seventeen",
+    "Eighteen",
+    "Nineteen",
+];
+
+const TENS: [&str; 10] = [
+    "", "", "Twenty", "Thirty", "Forty", "Fifty", "Sixty", "Seventy", "Eighty", "Ninety",
+];
+
+/// Groups the digits of an amount using the Indian numbering system, e.g. `5,00,000`.
+pub fn format_indian(amount: i64) -> String {
+    let digits = amount.unsigned_abs().to_string();
+    let sign = if amount < 0 { "-" } else { "" };
+
+    if digits.len() <= 3 {
+        return format!("{sign}{digits}");
+    }
+
+    let (head, last_three) = digits.split_at(digits.len() - 3);
+    let mut groups = Vec::new();
+    let mut rest = head;
+    while rest.len() > 2 {
+        let (left, right) = rest.split_at(rest.len() - 2);
+        groups.push(right);
+        rest = left;
+    }
+    groups.push(rest);
+    groups.reverse();
+
+    format!("{sign}{},{last_three}", groups.join(","))
+}
+
+/// Reads back an amount typed or pasted with separators, e.g. `1,00,000` or `₹ 5,000`.
+/// Returns `None` when the amount is too large to store.
+pub fn parse_amount(input: &str) -> Option<i64> {
+    let digits: String = input.chars().filter(char::is_ascii_digit).collect();
+    if digits.is_empty() {
+        return Some(0);
+    }
+
+    digits.parse().ok()
+}
+
+/// Shows a basis-point rate as a percentage without trailing zeros, e.g. 725 as `7.25`.
+pub fn format_rate(basis_points: i64) -> String {
+    let percent = format!("{:.2}", basis_points as f64 / 100.0);
+
+    percent
+        .trim_end_matches('0')
+        .trim_end_matches('.')
+        .to_string()
+}
+
+/// A basis-point rate for display, with its percent sign, e.g. `7%`, `7.25%` or `0%`.
+/// Form fields keep using `format_rate` so the raw number stays editable.
+pub fn format_percent(basis_points: i64) -> String {
+    format!("{}%", format_rate(basis_points))
+}
+
+/// Reads a percentage with up to two decimals, e.g. `7.25`, as basis points.
+pub fn parse_rate(input: &str) -> Option<i64> {
+    let percent: f64 = input.trim().parse().ok()?;
+    if !percent.is_finite() || percent < 0.0 {
+        return None;
+    }
+
+    Some((percent * 100.0).round() as i64)
+}
+
+/// Spells out an amount using lakhs and crores, e.g. "One Lakh Twenty Thousand".
+pub fn amount_to_words(amount: i64) -> String {
+    if amount == 0 {
+        return ONES[0].to_string();
+    }
+
+    let words = words(amount.unsigned_abs());
+    if amount < 0 {
+        format!("Minus {words}")
+    } else {
+        words
+    }
+}
+
+fn words(n: u64) -> String {
+    let mut parts = Vec::new();
+
+    let crores = n / 10_000_000;
+    if crores > 0 {
+        // anything above 99 crore is itself spelled out in lakhs and crores
+        parts.push(format!("{} Crore", words(crores)));
+    }
+
+    let rest = n % 10_000_000;
+    for (unit, name) in [(100_000, "Lakh"), (1_000, "Thousand"), (100, "Hundred")] {
+        let count = rest / unit % if unit == 100 { 10 } else { 100 };
+        if count > 0 {
+            parts.push(format!("{} {name}", below_hundred(count)));
+        }
+    }
+
+    let remainder = rest % 100;
+    if remainder > 0 {
+        parts.push(below_hundred(remainder));
+    }
+
+    parts.join(" ")
+}
+
+fn below_hundred(n: u64) -> String {
+    let n = n as usize;
+    if n < 20 {
+        ONES[n].to_string()
+    } else if n % 10 == 0 {
+        TENS[n / 10].to_string()
+    } else {
+        format!("{} {}", TENS[n / 10], ONES[n % 10])
+    }
+}
+
+/// Plain-text summary of an investment for sharing, one fact per line.
+pub fn share_summary(investment: &Investment) -> String {
+    let maturity_date = investment
+        .end_date
+        .map(|date| date.date_naive().format("%d-%m-%Y").to_string())
+        .unwrap_or_else(|| "-".to_string());
+
+    format!(
+        "{} ({}, {})\nInvested: {} at {}\nMatures: {}\nMaturity value: {}",
+        investment.inv_name,
+        investment.inv_type,
+        investment.return_type,
+        format_indian(investment.inv_amount),
+        format_percent(investment.return_rate),
+        maturity_date,
+        format_indian(investment.maturity_value()),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{TimeZone, Utc};
+
+    use super::*;
+
+    #[test]
+    fn words_use_lakhs_and_crores() {
+        assert_eq!(amount_to_words(1_20_000), "One Lakh Twenty Thousand");
+        assert_eq!(amount_to_words(2_50_00_000), "Two Crore Fifty Lakh");
+        assert_eq!(
+            amount_to_words(12_34_56_789),
+            "Twelve Crore Thirty Four Lakh Fifty Six Thousand Seven Hundred Eighty Nine"
+        );
+        assert_eq!(amount_to_words(0), "Zero");
+    }
+
+    #[test]
+    fn amounts_group_the_indian_way() {
+        assert_eq!(format_indian(500_000), "5,00,000");
+        assert_eq!(format_indian(999), "999");
+    }
+
+    #[test]
+    fn separators_are_ignored_when_parsing_amounts() {
+        assert_eq!(parse_amount("1,00,000"), Some(100_000));
+        assert_eq!(parse_amount("₹ 5,000"), Some(5_000));
+    }
+
+    #[test]
+    fn an_amount_past_i64_does_not_parse() {
+        assert_eq!(parse_amount("12345678901234567890"), None);
+        assert_eq!(parse_amount("9,22,33,72,03,68,54,77,5807"), Some(i64::MAX));
+    }
+
+    #[test]
+    fn percentages_drop_trailing_zeros() {
+        assert_eq!(format_percent(725), "7.25%");
+        assert_eq!(format_percent(700), "7%");
+        assert_eq!(format_percent(0), "0%");
+    }
+
+    fn sbi() -> Investment {
+        Investment {
+            inv_name: "SBI".into(),
+            inv_type: "FD".into(),
+            return_type: "Ordinary".into(),
+            return_rate: 725,
+            inv_amount: 100_000,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn a_summary_has_one_fact_per_line() {
+        let investment = Investment {
+            return_rate: 700,
+            start_date: Some(Utc.with_ymd_and_hms(2021, 1, 1, 0, 0, 0).unwrap()),
+            end_date: Some(Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap()),
+            ..sbi()
+        };
+
+        assert_eq!(
+            share_summary(&investment),
+            "SBI (FD, Ordinary)\nInvested: 1,00,000 at 7%\nMatures: 01-01-2024\nMaturity value: 1,21,000"
+        );
+    }
+
+    #[test]
+    fn an_undated_summary_shows_a_dash() {
+        assert_eq!(share_summary(&sbi()).lines().nth(2), Some("Matures: -"));
+    }
+}
@@ -1,55 +1,171 @@
 use std::collections::VecDeque;
+use std::fmt;
 
-use reqwasm::{http::Request, Error};
+use reqwasm::http::{Method, Request, Response};
+use serde::de::DeserializeOwned;
 
+use crate::state::Page;
 use types::*;
 
 #[allow(dead_code)]
-const BASE_URL: &str = "http://localhost:8080";
+const BASE_URL: &str = "http://localhost:8080/api/v1";
 
-pub async fn fetch_investments() -> Result<VecDeque<Investment>, Error> {
-    let response = Request::get(&format!("{BASE_URL}/invs")).send().await?;
-    response.json().await
+/// Why a call to the API failed.
+#[derive(Debug)]
+pub enum ApiError {
+    /// No response at all, e.g. the server is down or CORS refused it.
+    Network(String),
+    /// The server answered with a non-2xx status; the text is its error message.
+    Status(u16, String),
+    /// A 2xx response whose body wasn't what we expected.
+    Decode(String),
 }
 
-pub async fn create_investment(inv: String) -> Result<Investment, Error> {
-    let response = Request::post(&format!("{}/inv", BASE_URL))
-        .header("Content-Type", "application/json")
-        .body(inv) // Set the serialized JSON as the body
+impl fmt::Display for ApiError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ApiError::Network(message) => write!(f, "Couldn't reach the server: {message}"),
+            ApiError::Status(status, message) if message.is_empty() => {
+                write!(f, "The server answered {status}")
+            }
+            ApiError::Status(_, message) => write!(f, "{message}"),
+            ApiError::Decode(message) => write!(f, "Unexpected response: {message}"),
+        }
+    }
+}
+
+/// Sends a request to `path` with an optional JSON `body` and turns a non-2xx
+/// answer into `ApiError::Status` carrying the server's message.
+async fn send(method: Method, path: &str, body: Option<String>) -> Result<Response, ApiError> {
+    let mut request = Request::new(&format!("{BASE_URL}{path}")).method(method);
+    if let Some(body) = body {
+        request = request
+            .header("Content-Type", "application/json")
+            .body(body);
+    }
+    let response = request
         .send()
-        .await?;
+        .await
+        .map_err(|e| ApiError::Network(e.to_string()))?;
 
-    // Log the response body
-    let response_body = response.text().await?;
+    if !response.ok() {
+        let body = response.text().await.unwrap_or_default();
+        return Err(ApiError::Status(response.status(), problem_message(body)));
+    }
 
-    // Parse the response body as JSON
-    let inv: Investment = serde_json::from_str(&response_body)?;
+    Ok(response)
+}
 
-    Ok(inv)
+/// The message to show for an error body: the problem's detail, naming the
+/// field at fault, or the body as it came when it isn't a problem document.
+fn problem_message(body: String) -> String {
+    match serde_json::from_str::<Problem>(&body) {
+        Ok(Problem {
+            detail,
+            field: Some(field),
+            ..
+        }) => format!("{field}: {detail}"),
+        Ok(problem) => problem.detail,
+        Err(_) => body,
+    }
 }
 
-pub async fn edit_investment(inv: String) -> Result<Investment, Error> {
-    let response = Request::patch(&format!("{}/inv", BASE_URL))
-        .header("Content-Type", "application/json")
-        .body(inv) // Set the serialized JSON as the body
-        .send()
-        .await?;
+async fn decode<T: DeserializeOwned>(response: Response) -> Result<T, ApiError> {
+    response
+        .json()
+        .await
+        .map_err(|e| ApiError::Decode(e.to_string()))
+}
 
-    // Log the response body
-    let response_body = response.text().await?;
+/// `send`, then the JSON body of the response as `T`.
+async fn request<T: DeserializeOwned>(
+    method: Method,
+    path: &str,
+    body: Option<String>,
+) -> Result<T, ApiError> {
+    decode(send(method, path, body).await?).await
+}
 
-    // Parse the response body as JSON
-    let inv: Investment = serde_json::from_str(&response_body)?;
+/// One page of investments. Without a `limit` the server picks its default page size.
+pub async fn fetch_investments(
+    limit: Option<usize>,
+    offset: usize,
+) -> Result<(VecDeque<Investment>, Page), ApiError> {
+    let mut path = format!("/invs?offset={offset}");
+    if let Some(limit) = limit {
+        path.push_str(&format!("&limit={limit}"));
+    }
+    let response = send(Method::GET, &path, None).await?;
+    let headers = response.headers();
+    let page = Page {
+        limit: headers
+            .get("X-Page-Size")
+            .and_then(|limit| limit.parse().ok())
+            .unwrap_or_default(),
+        offset,
+        has_more: headers.get("X-Result-Truncated").as_deref() == Some("true"),
+    };
 
-    Ok(inv)
+    Ok((decode(response).await?, page))
 }
 
-pub async fn delete_investment(id: String) -> Result<Record, Error> {
-    let response = Request::delete(&format!("{}/inv", BASE_URL))
-        .header("Content-Type", "application/json")
-        .body(id) // Set the serialized JSON as the body
-        .send()
-        .await?;
+pub async fn fetch_investor_names() -> Result<Vec<String>, ApiError> {
+    request(Method::GET, "/invs/names", None).await
+}
+
+/// Existing investments with the same name, amount and start day (`YYYY-MM-DD`).
+pub async fn check_duplicate(
+    name: &str,
+    amount: i64,
+    start: &str,
+) -> Result<Vec<Investment>, ApiError> {
+    let name = String::from(web_sys::js_sys::encode_uri_component(name));
+    let path = format!("/invs/check-duplicate?name={name}&amount={amount}&start={start}");
+    request(Method::GET, &path, None).await
+}
+
+pub async fn create_investment(inv: String) -> Result<Investment, ApiError> {
+    request(Method::POST, "/inv", Some(inv)).await
+}
+
+pub async fn edit_investment(inv: String) -> Result<Investment, ApiError> {
+    request(Method::PATCH, "/inv", Some(inv)).await
+}
+
+/// `fields` holds only the changed fields.
+pub async fn patch_investment(id: String, fields: String) -> Result<Investment, ApiError> {
+    request(Method::PATCH, &format!("/inv/{id}"), Some(fields)).await
+}
+
+/// `inv` is the successor investment.
+pub async fn renew_investment(id: String, inv: String) -> Result<Renewal, ApiError> {
+    request(Method::POST, &format!("/inv/{id}/renew"), Some(inv)).await
+}
+
+pub async fn archive_investment(id: String) -> Result<Investment, ApiError> {
+    request(Method::POST, &format!("/inv/{id}/archive"), None).await
+}
+
+/// `ids` lists the ids to archive, e.g. `["investment:abc"]`.
+pub async fn archive_investments(ids: String) -> Result<AffectedRows, ApiError> {
+    request(Method::POST, "/invs/archive", Some(ids)).await
+}
+
+/// `ids` lists every id on the page starting `offset` rows in, in the new order.
+pub async fn reorder_investments(ids: String, offset: usize) -> Result<AffectedRows, ApiError> {
+    request(
+        Method::PATCH,
+        &format!("/invs/reorder?offset={offset}"),
+        Some(ids),
+    )
+    .await
+}
+
+pub async fn delete_investment(id: String) -> Result<AffectedRows, ApiError> {
+    request(Method::DELETE, "/inv", Some(id)).await
+}
 
-    response.json().await
+/// `ids` lists the ids to delete, e.g. `["investment:abc"]`.
+pub async fn delete_investments(ids: String) -> Result<AffectedRows, ApiError> {
+    request(Method::DELETE, "/invs", Some(ids)).await
 }
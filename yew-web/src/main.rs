@@ -1,8 +1,13 @@
 mod app;
 mod components;
 mod controllers;
+mod format;
 mod inv_api;
+mod messages;
+mod platform;
+mod preferences;
 mod state;
+mod unload_guard;
 
 use app::App;
 
@@ -0,0 +1,129 @@
+//! Text for form validation errors.
+//!
+//! Validators report a `ValidationMessage`; a `MessageCatalog` turns it into
+//! the string shown to the user. Swap the catalog to show another language.
+
+use std::rc::Rc;
+
+use crate::format::format_indian;
+use types::validation::{MAX_YEAR, MIN_YEAR};
+
+/// A validation failure, identified by the form field id it concerns.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ValidationMessage {
+    Blank(&'static str),
+    InvAmountAboveReturn,
+    ReturnAmountBelowInv,
+    YearOutOfRange(&'static str),
+    AmountTooLarge,
+    /// Not an error: the return amount is far from the computed one given here.
+    ReturnAmountMismatch(i64),
+}
+
+#[derive(Clone)]
+pub struct MessageCatalog {
+    text: Rc<dyn Fn(ValidationMessage) -> String>,
+}
+
+impl MessageCatalog {
+    pub fn new(text: impl Fn(ValidationMessage) -> String + 'static) -> Self {
+        Self {
+            text: Rc::new(text),
+        }
+    }
+
+    pub fn get(&self, message: ValidationMessage) -> String {
+        (self.text)(message)
+    }
+}
+
+impl Default for MessageCatalog {
+    fn default() -> Self {
+        Self::new(english)
+    }
+}
+
+/// Two catalogs are equal when they share the same translation function.
+impl PartialEq for MessageCatalog {
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.text, &other.text)
+    }
+}
+
+pub fn english(message: ValidationMessage) -> String {
+    match message {
+        ValidationMessage::Blank(field) => format!("{} can not be blank", english_label(field)),
+        ValidationMessage::InvAmountAboveReturn => {
+            "Investment Amount can not be more than Return Amount".to_string()
+        }
+        ValidationMessage::ReturnAmountBelowInv => {
+            "Return Amount can not be less than Investment Amount".to_string()
+        }
+        ValidationMessage::AmountTooLarge => "Amount too large".to_string(),
+        ValidationMessage::ReturnAmountMismatch(expected) => format!(
+            "Expected about {} from the rate and dates; check the Return Amount",
+            format_indian(expected)
+        ),
+        ValidationMessage::YearOutOfRange(field) => format!(
+            "{} must be between {MIN_YEAR} and {MAX_YEAR}",
+            english_label(field)
+        ),
+    }
+}
+
+fn english_label(field: &str) -> &str {
+    match field {
+        "inv-name" => "Investment Name",
+        "name" => "Name",
+        "inv-type" => "Investment Type",
+        "return-type" => "Return Type",
+        "inv-amount" => "Investment Amount",
+        "return-amount" => "Return Amount",
+        "return-rate" => "Return Rate",
+        "start-date" => "Start Date",
+        "end-date" => "End Date",
+        other => other,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn english_names_the_field_by_its_label() {
+        assert_eq!(
+            english(ValidationMessage::Blank("inv-name")),
+            "Investment Name can not be blank"
+        );
+        assert_eq!(
+            english(ValidationMessage::YearOutOfRange("end-date")),
+            format!("End Date must be between {MIN_YEAR} and {MAX_YEAR}")
+        );
+    }
+
+    #[test]
+    fn a_replaced_catalog_supplies_its_own_text() {
+        let catalog = MessageCatalog::new(|message| match message {
+            ValidationMessage::AmountTooLarge => "राशि बहुत बड़ी है".to_string(),
+            other => english(other),
+        });
+
+        assert_eq!(
+            catalog.get(ValidationMessage::AmountTooLarge),
+            "राशि बहुत बड़ी है"
+        );
+        assert_eq!(
+            catalog.get(ValidationMessage::Blank("name")),
+            "Name can not be blank"
+        );
+    }
+
+    #[test]
+    fn catalogs_are_equal_only_when_they_share_a_function() {
+        let catalog = MessageCatalog::default();
+
+        assert!(catalog == catalog.clone());
+        assert!(catalog != MessageCatalog::default());
+    }
+}
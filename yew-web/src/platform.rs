@@ -0,0 +1,135 @@
+//! Fallible access to browser APIs.
+//!
+//! Nothing here panics: when `window`, `localStorage` or `matchMedia` are
+//! missing (tests, private browsing, non-browser hosts) callers get `None`
+//! and fall back to defaults. Console output goes through the `log` crate.
+
+use web_sys::wasm_bindgen::{closure::Closure, JsCast};
+use web_sys::{Element, HtmlElement, KeyboardEvent, Storage, Window};
+use yew::NodeRef;
+
+pub fn window() -> Option<Window> {
+    web_sys::window()
+}
+
+pub fn document_element() -> Option<Element> {
+    window()?.document()?.document_element()
+}
+
+pub fn local_storage() -> Option<Storage> {
+    window()?.local_storage().ok()?
+}
+
+pub fn get_item(key: &str) -> Option<String> {
+    local_storage()?.get_item(key).ok()?
+}
+
+/// Returns `None` when storage is unavailable or the write was refused (e.g. quota).
+pub fn set_item(key: &str, value: &str) -> Option<()> {
+    local_storage()?.set_item(key, value).ok()
+}
+
+pub fn media_matches(query: &str) -> Option<bool> {
+    Some(window()?.match_media(query).ok()??.matches())
+}
+
+pub fn prefers_dark_mode() -> Option<bool> {
+    media_matches("(prefers-color-scheme: dark)")
+}
+
+/// Adds or removes the `dark` class on the root element that Tailwind keys dark mode on.
+pub fn set_dark_class(enabled: bool) -> Option<()> {
+    let class_list = document_element()?.class_list();
+    let result = if enabled {
+        class_list.add_1("dark")
+    } else {
+        class_list.remove_1("dark")
+    };
+
+    result.ok()
+}
+
+/// True while an input, textarea or select has focus, i.e. the user may be typing.
+pub fn is_editing() -> bool {
+    let active = window()
+        .and_then(|window| window.document())
+        .and_then(|document| document.active_element());
+
+    active.map_or(false, |element| is_field_tag(&element.tag_name()))
+}
+
+/// Whether an element with this tag name takes typed text.
+fn is_field_tag(tag_name: &str) -> bool {
+    matches!(tag_name, "INPUT" | "TEXTAREA" | "SELECT")
+}
+
+/// A `keydown` listener on the window, removed again when dropped.
+pub struct KeyDownListener {
+    listener: Closure<dyn FnMut(KeyboardEvent)>,
+}
+
+impl Drop for KeyDownListener {
+    fn drop(&mut self) {
+        if let Some(window) = window() {
+            let _ = window.remove_event_listener_with_callback(
+                "keydown",
+                self.listener.as_ref().unchecked_ref(),
+            );
+        }
+    }
+}
+
+/// Calls `callback` for every key pressed anywhere on the page, until the returned listener is dropped.
+pub fn on_key_down(callback: impl FnMut(KeyboardEvent) + 'static) -> Option<KeyDownListener> {
+    let listener = Closure::<dyn FnMut(KeyboardEvent)>::new(callback);
+    window()?
+        .add_event_listener_with_callback("keydown", listener.as_ref().unchecked_ref())
+        .ok()?;
+
+    Some(KeyDownListener { listener })
+}
+
+/// Moves focus to the element behind `node_ref`, unless the user is typing elsewhere.
+pub fn focus_unless_editing(node_ref: &NodeRef) -> Option<()> {
+    if is_editing() {
+        return None;
+    }
+
+    node_ref.cast::<HtmlElement>()?.focus().ok()
+}
+
+/// Writes `text` to the system clipboard. `None` when the Clipboard API is
+/// missing or the browser refused, e.g. no permission or an insecure origin.
+pub async fn copy_to_clipboard(text: &str) -> Option<()> {
+    let promise = window()?.navigator().clipboard()?.write_text(text);
+
+    wasm_bindgen_futures::JsFuture::from(promise)
+        .await
+        .ok()
+        .map(|_| ())
+}
+
+/// Runs `callback` once after `millis` milliseconds. `None` when no timer could be set.
+pub fn set_timeout(millis: i32, callback: impl FnOnce() + 'static) -> Option<()> {
+    let callback = Closure::once_into_js(callback);
+
+    window()?
+        .set_timeout_with_callback_and_timeout_and_arguments_0(callback.unchecked_ref(), millis)
+        .ok()
+        .map(|_| ())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_form_fields_count_as_editing() {
+        for tag_name in ["INPUT", "TEXTAREA", "SELECT"] {
+            assert!(is_field_tag(tag_name), "{tag_name}");
+        }
+        for tag_name in ["BODY", "BUTTON", "TD"] {
+            assert!(!is_field_tag(tag_name), "{tag_name}");
+        }
+    }
+}
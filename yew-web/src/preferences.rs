@@ -0,0 +1,120 @@
+//! User preferences, kept in `localStorage` as one JSON object.
+//!
+//! `App` loads them once on start and hands them out through a
+//! `PreferencesContext`; whoever changes a setting sends the new value to
+//! `update`, which stores it. Missing or unreadable storage means defaults.
+
+use serde::{Deserialize, Serialize};
+use yew::Callback;
+
+use crate::platform;
+
+const PREFERENCES_KEY: &str = "preferences";
+
+/// Where the theme was stored before it moved into the preferences.
+const LEGACY_THEME_KEY: &str = "theme";
+
+#[derive(Clone, PartialEq, Debug, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Preferences {
+    /// `None` follows the OS colour scheme.
+    pub dark_mode: Option<bool>,
+    /// Leave matured investments out of the list.
+    pub hide_matured: bool,
+    /// Show returns net of tax instead of gross.
+    pub show_net: bool,
+    /// Rows per list page; `None` uses the server's default.
+    pub page_size: Option<usize>,
+}
+
+impl Preferences {
+    /// The stored preferences, or the defaults when there are none or they can't be read.
+    pub fn load() -> Self {
+        let stored = platform::get_item(PREFERENCES_KEY);
+        // only read when there is nothing newer
+        let legacy_theme = stored
+            .is_none()
+            .then(|| platform::get_item(LEGACY_THEME_KEY))
+            .flatten();
+
+        Self::parse(stored.as_deref(), legacy_theme.as_deref())
+    }
+
+    pub fn save(&self) {
+        if platform::set_item(PREFERENCES_KEY, &self.to_stored()).is_none() {
+            log::warn!("could not persist preferences, they will reset on reload");
+        }
+    }
+
+    /// The preferences in `stored`, as written by `to_stored`; without it, the
+    /// defaults with the theme from before it moved into the preferences.
+    fn parse(stored: Option<&str>, legacy_theme: Option<&str>) -> Self {
+        let Some(stored) = stored else {
+            let dark_mode = match legacy_theme {
+                Some("dark") => Some(true),
+                Some("light") => Some(false),
+                _ => None,
+            };
+            return Self {
+                dark_mode,
+                ..Self::default()
+            };
+        };
+
+        serde_json::from_str(stored).unwrap_or_else(|e| {
+            log::warn!("ignoring unreadable preferences: {e}");
+            Self::default()
+        })
+    }
+
+    fn to_stored(&self) -> String {
+        serde_json::to_string(self).unwrap_or_default()
+    }
+}
+
+/// The preferences in effect and the way to change them.
+#[derive(Clone, PartialEq)]
+pub struct PreferencesContext {
+    pub preferences: Preferences,
+    /// Replaces the preferences and stores them.
+    pub update: Callback<Preferences>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn nothing_stored_means_the_defaults() {
+        assert_eq!(Preferences::parse(None, None), Preferences::default());
+    }
+
+    #[test]
+    fn the_old_theme_is_picked_up_when_nothing_newer_is_stored() {
+        assert_eq!(Preferences::parse(None, Some("dark")).dark_mode, Some(true));
+        assert_eq!(Preferences::parse(Some("{}"), Some("dark")).dark_mode, None);
+    }
+
+    #[test]
+    fn unreadable_preferences_mean_the_defaults() {
+        assert_eq!(
+            Preferences::parse(Some("{not json"), None),
+            Preferences::default()
+        );
+    }
+
+    #[test]
+    fn stored_preferences_read_back_unchanged() {
+        let preferences = Preferences {
+            dark_mode: Some(false),
+            hide_matured: true,
+            show_net: true,
+            page_size: Some(50),
+        };
+
+        assert_eq!(
+            Preferences::parse(Some(&preferences.to_stored()), None),
+            preferences
+        );
+    }
+}
@@ -1,4 +1,4 @@
-use std::collections::VecDeque;
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
 use std::rc::Rc;
 
 use surrealdb::sql::Thing;
@@ -12,11 +12,87 @@ pub enum InvestmentAction {
     Add(Investment),
     Edit(Investment),
     Delete(Thing),
+    /// Several `Add`s applied at once, so the list re-renders once.
+    AddMany(Vec<Investment>),
+    /// Several `Delete`s applied at once, so the list re-renders once.
+    RemoveMany(Vec<Thing>),
+    /// Forget everything loaded, e.g. on logout.
+    Clear,
+    /// Which page of the list the loaded investments are.
+    SetPage(Page),
+    /// Ends the highlight with this generation; a newer one is left alone.
+    ClearHighlight(u32),
+}
+
+/// The investment that was just added or edited, flashed so it's easy to spot.
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct Highlight {
+    /// `None` once the flash is over.
+    pub id: Option<Thing>,
+    /// Bumped on every change, so each one gets its own flash and its own timer.
+    pub generation: u32,
+}
+
+/// Where the loaded investments sit in the server's list.
+#[derive(Clone, Copy, PartialEq, Debug, Default)]
+pub struct Page {
+    /// Rows per page; 0 until the server has told us its default.
+    pub limit: usize,
+    pub offset: usize,
+    /// More rows follow this page.
+    pub has_more: bool,
+}
+
+/// The lower-case words of every investment's `name` and `inv_name`, each
+/// pointing at the positions in `investments` that contain it. Rebuilt by
+/// every action that changes the list, so it can't go stale.
+#[derive(Clone, Default)]
+pub struct SearchIndex(BTreeMap<String, Vec<usize>>);
+
+impl SearchIndex {
+    fn build(investments: &VecDeque<Investment>) -> Self {
+        let mut words: BTreeMap<String, Vec<usize>> = BTreeMap::new();
+        for (position, investment) in investments.iter().enumerate() {
+            for word in search_words(&investment.name).chain(search_words(&investment.inv_name)) {
+                let positions = words.entry(word).or_default();
+                if positions.last() != Some(&position) {
+                    positions.push(position);
+                }
+            }
+        }
+
+        Self(words)
+    }
+
+    /// Positions with a word starting with each word of `query`; `None` for an empty query.
+    fn positions(&self, query: &str) -> Option<BTreeSet<usize>> {
+        search_words(query).fold(None, |found: Option<BTreeSet<usize>>, prefix| {
+            let matching: BTreeSet<usize> = self
+                .0
+                .range(prefix.clone()..)
+                .take_while(|(word, _)| word.starts_with(&prefix))
+                .flat_map(|(_, positions)| positions.iter().copied())
+                .collect();
+            Some(match found {
+                Some(found) => found.intersection(&matching).copied().collect(),
+                None => matching,
+            })
+        })
+    }
+}
+
+fn search_words(text: &str) -> impl Iterator<Item = String> + '_ {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
 }
 
 /// reducer's State
 pub struct InvestmentState {
     pub investments: VecDeque<Investment>,
+    pub page: Page,
+    pub highlight: Highlight,
+    index: SearchIndex,
 }
 
 /// Implementation by default when starting the application
@@ -24,6 +100,23 @@ impl Default for InvestmentState {
     fn default() -> Self {
         Self {
             investments: VecDeque::from([]),
+            page: Page::default(),
+            highlight: Highlight::default(),
+            index: SearchIndex::default(),
+        }
+    }
+}
+
+impl InvestmentState {
+    /// The loaded investments with a name or investment name word starting
+    /// with each word of `query`, in list order. All of them for an empty query.
+    pub fn search(&self, query: &str) -> VecDeque<Investment> {
+        match self.index.positions(query) {
+            Some(positions) => positions
+                .into_iter()
+                .filter_map(|position| self.investments.get(position).cloned())
+                .collect(),
+            None => self.investments.clone(),
         }
     }
 }
@@ -34,11 +127,73 @@ impl Reducible for InvestmentState {
     type Action = InvestmentAction;
 
     fn reduce(self: Rc<Self>, action: Self::Action) -> Rc<Self> {
+        // adding or editing flashes that investment's row
+        let changed_id = match &action {
+            InvestmentAction::Add(investment) | InvestmentAction::Edit(investment) => {
+                investment.id.clone()
+            }
+            // a batch flashes its first investment
+            InvestmentAction::AddMany(batch) => batch.first().and_then(|first| first.id.clone()),
+            _ => None,
+        };
+        let highlight = match changed_id {
+            Some(id) => Highlight {
+                id: Some(id),
+                generation: self.highlight.generation.wrapping_add(1),
+            },
+            None => self.highlight.clone(),
+        };
+
         let next_investments = match action {
-            InvestmentAction::Set(investments) => investments,
+            InvestmentAction::Clear => return Self::default().into(),
+            InvestmentAction::SetPage(page) => {
+                return Self {
+                    investments: self.investments.clone(),
+                    page,
+                    highlight: self.highlight.clone(),
+                    index: self.index.clone(),
+                }
+                .into()
+            }
+            InvestmentAction::ClearHighlight(generation) => {
+                let mut highlight = self.highlight.clone();
+                if highlight.generation == generation {
+                    highlight.id = None;
+                }
+                return Self {
+                    investments: self.investments.clone(),
+                    page: self.page,
+                    highlight,
+                    index: self.index.clone(),
+                }
+                .into();
+            }
+            InvestmentAction::Set(investments) => {
+                // replaces the whole list; a record listed twice is kept once
+                let mut seen = Vec::new();
+                investments
+                    .into_iter()
+                    .filter(|investment| match &investment.id {
+                        Some(id) if seen.contains(id) => false,
+                        Some(id) => {
+                            seen.push(id.clone());
+                            true
+                        }
+                        None => true,
+                    })
+                    .collect()
+            }
             InvestmentAction::Add(investment) => {
                 let mut investments = self.investments.clone();
-                investments.push_front(investment);
+                add(&mut investments, investment);
+                investments
+            }
+            InvestmentAction::AddMany(batch) => {
+                let mut investments = self.investments.clone();
+                // the first of the batch ends up on top
+                for investment in batch.into_iter().rev() {
+                    add(&mut investments, investment);
+                }
                 investments
             }
             InvestmentAction::Edit(edited_inv) => {
@@ -56,11 +211,151 @@ impl Reducible for InvestmentState {
                 investments.retain(|investment| investment.id != Some(id.clone()));
                 investments
             }
+            InvestmentAction::RemoveMany(ids) => {
+                let mut investments = self.investments.clone();
+                investments
+                    .retain(|investment| !matches!(&investment.id, Some(id) if ids.contains(id)));
+                investments
+            }
         };
 
         Self {
+            index: SearchIndex::build(&next_investments),
             investments: next_investments,
+            page: self.page,
+            highlight,
         }
         .into()
     }
 }
+
+/// Puts `investment` on top, or updates it in place when we already hold it
+/// (e.g. a retried create).
+fn add(investments: &mut VecDeque<Investment>, investment: Investment) {
+    match investments
+        .iter_mut()
+        .find(|existing| investment.id.is_some() && existing.id == investment.id)
+    {
+        Some(existing) => *existing = investment,
+        None => investments.push_front(investment),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn investment(key: &str, name: &str, inv_name: &str) -> Investment {
+        Investment {
+            id: Some(Thing::from(("investment", key))),
+            name: name.into(),
+            inv_name: inv_name.into(),
+            ..Default::default()
+        }
+    }
+
+    fn id(key: &str) -> Thing {
+        Thing::from(("investment", key))
+    }
+
+    fn keys(state: &InvestmentState) -> Vec<String> {
+        state
+            .investments
+            .iter()
+            .filter_map(|investment| investment.id.as_ref().map(|id| id.id.to_raw()))
+            .collect()
+    }
+
+    fn loaded(investments: Vec<Investment>) -> Rc<InvestmentState> {
+        Rc::new(InvestmentState::default()).reduce(InvestmentAction::Set(investments.into()))
+    }
+
+    #[test]
+    fn clear_forgets_everything() {
+        let state = loaded(vec![investment("a", "Asha", "SBI")])
+            .reduce(InvestmentAction::SetPage(Page {
+                limit: 25,
+                offset: 25,
+                has_more: true,
+            }))
+            .reduce(InvestmentAction::Clear);
+
+        assert!(state.investments.is_empty());
+        assert_eq!(state.page, Page::default());
+        assert!(state.search("asha").is_empty());
+    }
+
+    #[test]
+    fn set_keeps_a_record_listed_twice_once() {
+        let state = loaded(vec![
+            investment("a", "Asha", "SBI"),
+            investment("b", "Ravi", "HDFC"),
+            investment("a", "Asha", "SBI"),
+        ]);
+
+        assert_eq!(keys(&state), ["a", "b"]);
+    }
+
+    #[test]
+    fn add_many_adds_the_whole_batch() {
+        let state =
+            loaded(vec![investment("a", "Asha", "SBI")]).reduce(InvestmentAction::AddMany(vec![
+                investment("b", "Ravi", "HDFC"),
+                investment("c", "Meera", "ICICI"),
+            ]));
+        // the first of the batch ends up on top
+        assert_eq!(keys(&state), ["b", "c", "a"]);
+    }
+
+    #[test]
+    fn remove_many_removes_the_whole_batch() {
+        let state = loaded(vec![
+            investment("a", "Asha", "SBI"),
+            investment("b", "Ravi", "HDFC"),
+            investment("c", "Meera", "ICICI"),
+        ])
+        .reduce(InvestmentAction::RemoveMany(vec![
+            id("a"),
+            id("c"),
+            id("z"),
+        ]));
+
+        assert_eq!(keys(&state), ["b"]);
+        assert!(state.search("asha").is_empty());
+    }
+
+    #[test]
+    fn a_stale_timer_leaves_a_newer_highlight_in_place() {
+        let state =
+            loaded(Vec::new()).reduce(InvestmentAction::Add(investment("a", "Asha", "SBI")));
+        let first = state.highlight.generation;
+        let state = state.reduce(InvestmentAction::Edit(investment("a", "Asha", "SBI Flexi")));
+
+        let state = state.reduce(InvestmentAction::ClearHighlight(first));
+        assert_eq!(state.highlight.id, Some(id("a")));
+
+        let latest = state.highlight.generation;
+        let state = state.reduce(InvestmentAction::ClearHighlight(latest));
+        assert_eq!(state.highlight.id, None);
+    }
+
+    #[test]
+    fn search_follows_every_change() {
+        let state = loaded(vec![
+            investment("a", "Asha", "SBI Tax Saver"),
+            investment("b", "Ravi", "HDFC Monthly"),
+        ]);
+        assert_eq!(state.search("tax").len(), 1);
+        assert_eq!(state.search("").len(), 2);
+
+        let state = state.reduce(InvestmentAction::Edit(investment("a", "Asha", "SBI Flexi")));
+        assert!(state.search("tax").is_empty());
+        assert_eq!(state.search("fle").len(), 1);
+
+        let state = state.reduce(InvestmentAction::Delete(id("b")));
+        assert!(state.search("ravi").is_empty());
+        // positions are rebuilt, so the remaining row is still found
+        assert_eq!(keys(&state), ["a"]);
+        assert_eq!(state.search("asha sbi").len(), 1);
+    }
+}
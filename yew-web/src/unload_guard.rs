@@ -0,0 +1,86 @@
+//! Warns before the page is closed or reloaded while a form holds unsaved edits.
+//!
+//! Each form owns an `UnloadGuard` and flags it dirty while edits are pending.
+//! A single `beforeunload` listener prompts as long as any guard is dirty.
+//! There is no client-side routing yet; route changes should consult
+//! `has_unsaved_changes` once there is.
+
+use std::cell::Cell;
+
+use web_sys::wasm_bindgen::{closure::Closure, JsCast};
+use web_sys::BeforeUnloadEvent;
+
+use crate::platform;
+
+thread_local! {
+    static DIRTY_FORMS: Cell<usize> = Cell::new(0);
+    static LISTENER_INSTALLED: Cell<bool> = Cell::new(false);
+}
+
+#[derive(Default, PartialEq)]
+pub struct UnloadGuard {
+    dirty: bool,
+}
+
+impl UnloadGuard {
+    pub fn set_dirty(&mut self, dirty: bool) {
+        if self.dirty == dirty {
+            return;
+        }
+        self.dirty = dirty;
+
+        DIRTY_FORMS.with(|count| {
+            if dirty {
+                count.set(count.get() + 1);
+            } else {
+                count.set(count.get().saturating_sub(1));
+            }
+        });
+
+        if dirty {
+            install_listener();
+        }
+    }
+}
+
+/// A copy starts clean: only the original is counted by the page-level guard.
+impl Clone for UnloadGuard {
+    fn clone(&self) -> Self {
+        Self::default()
+    }
+}
+
+impl Drop for UnloadGuard {
+    fn drop(&mut self) {
+        self.set_dirty(false);
+    }
+}
+
+pub fn has_unsaved_changes() -> bool {
+    DIRTY_FORMS.with(|count| count.get() > 0)
+}
+
+fn install_listener() {
+    if LISTENER_INSTALLED.with(|installed| installed.get()) {
+        return;
+    }
+    let Some(window) = platform::window() else {
+        return;
+    };
+
+    let listener = Closure::<dyn FnMut(BeforeUnloadEvent)>::new(|event: BeforeUnloadEvent| {
+        if has_unsaved_changes() {
+            event.prevent_default();
+            event.set_return_value("You have unsaved changes.");
+        }
+    });
+
+    if window
+        .add_event_listener_with_callback("beforeunload", listener.as_ref().unchecked_ref())
+        .is_ok()
+    {
+        // the listener lives as long as the page
+        listener.forget();
+        LISTENER_INSTALLED.with(|installed| installed.set(true));
+    }
+}
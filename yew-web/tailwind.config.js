@@ -14,6 +14,7 @@ module.exports = {
       animation: {
         blob: "blob 7s infinite",
         blob2: "blob2 8s infinite",
+        flash: "flash 2s ease-out",
       },
       keyframes: {
         blob: {
@@ -44,6 +45,14 @@ module.exports = {
             transform: "translate(0px, 0px) scale(1)",
           },
         },
+        flash: {
+          "0%": {
+            backgroundColor: "var(--accent-200)",
+          },
+          "100%": {
+            backgroundColor: "transparent",
+          },
+        },
       },
       boxShadow: {
         'white-md': '0 4px 6px -1px rgba(255, 255, 255, 0.1), 0 2px 4px -1px rgba(255, 255, 255, 0.06)',